@@ -0,0 +1,150 @@
+use crate::configuration::manifest::ValueType;
+use liquid::model::Value as LqValue;
+use liquid::ValueView;
+use std::cmp::Ordering;
+
+/// Compares scalar values, numbers (including numeric strings) are compared
+/// as numbers and other scalars as strings.
+pub fn compare(value: &LqValue, other: &LqValue) -> Result<Ordering, String> {
+    match (value.as_scalar(), other.as_scalar()) {
+        (Some(left), Some(right)) => match (left.to_float(), right.to_float()) {
+            (Some(left), Some(right)) => left
+                .partial_cmp(&right)
+                .ok_or_else(|| format!("Cannot compare {} with {}", left, right)),
+            _ => Ok(left.to_kstr().as_str().cmp(right.to_kstr().as_str())),
+        },
+        _ => Err(format!(
+            "Cannot compare {} with {}",
+            value.type_name(),
+            other.type_name()
+        )),
+    }
+}
+
+/// Checks that string contains substring, array contains element
+/// or object contains key.
+pub fn contains(value: &LqValue, item: &LqValue) -> Result<bool, String> {
+    match value {
+        LqValue::Scalar(scalar) => Ok(scalar.to_kstr().contains(item.to_kstr().as_str())),
+        LqValue::Array(array) => Ok(array.contains(item)),
+        LqValue::Object(object) => Ok(object.contains_key(item.to_kstr().as_str())),
+        _ => Err(format!(
+            "Cannot check that {} contains value",
+            value.type_name()
+        )),
+    }
+}
+
+/// Checks string prefix or the first element of array
+pub fn starts_with(value: &LqValue, item: &LqValue) -> Result<bool, String> {
+    match value {
+        LqValue::Scalar(scalar) => Ok(scalar.to_kstr().starts_with(item.to_kstr().as_str())),
+        LqValue::Array(array) => Ok(array.first() == Some(item)),
+        _ => Err(format!("Cannot check start of {}", value.type_name())),
+    }
+}
+
+/// Checks string suffix or the last element of array
+pub fn ends_with(value: &LqValue, item: &LqValue) -> Result<bool, String> {
+    match value {
+        LqValue::Scalar(scalar) => Ok(scalar.to_kstr().ends_with(item.to_kstr().as_str())),
+        LqValue::Array(array) => Ok(array.last() == Some(item)),
+        _ => Err(format!("Cannot check end of {}", value.type_name())),
+    }
+}
+
+/// Returns number of characters of string, elements of array or keys of object
+pub fn length(value: &LqValue) -> Result<usize, String> {
+    match value {
+        LqValue::Scalar(scalar) if scalar.type_name() == "string" => {
+            Ok(scalar.to_kstr().chars().count())
+        }
+        LqValue::Array(array) => Ok(array.len()),
+        LqValue::Object(object) => Ok(object.len()),
+        _ => Err(format!("Cannot get length of {}", value.type_name())),
+    }
+}
+
+pub fn is_type(value: &LqValue, value_type: &ValueType) -> bool {
+    match (value, value_type) {
+        (LqValue::Nil, ValueType::Nil) => true,
+        (LqValue::Array(_), ValueType::Array) => true,
+        (LqValue::Object(_), ValueType::Object) => true,
+        (LqValue::Scalar(scalar), value_type) => matches!(
+            (scalar.type_name(), value_type),
+            ("string", ValueType::String)
+                | ("boolean", ValueType::Boolean)
+                | ("whole number", ValueType::Integer)
+                | ("whole number", ValueType::Number)
+                | ("fractional number", ValueType::Float)
+                | ("fractional number", ValueType::Number)
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_compare_numbers_and_strings() {
+        let compare_values = |left: LqValue, right: LqValue| compare(&left, &right).unwrap();
+
+        assert_eq!(
+            compare_values(LqValue::scalar(2), LqValue::scalar(10)),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_values(LqValue::scalar(2.5), LqValue::scalar(2)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_values(LqValue::scalar("10"), LqValue::scalar(9)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_values(LqValue::scalar("abc"), LqValue::scalar("abd")),
+            Ordering::Less
+        );
+        assert!(compare(&LqValue::Nil, &LqValue::scalar(1)).is_err());
+        assert!(compare(&LqValue::Array(vec![]), &LqValue::scalar(1)).is_err());
+    }
+
+    #[test]
+    fn test_contains_and_affixes() {
+        let array = LqValue::Array(vec![LqValue::scalar("a"), LqValue::scalar("b")]);
+        let object: liquid::Object = vec![("key".into(), LqValue::scalar(1))]
+            .into_iter()
+            .collect();
+        let object = LqValue::Object(object);
+
+        assert!(contains(&LqValue::scalar("hello world"), &LqValue::scalar("lo w")).unwrap());
+        assert!(contains(&array, &LqValue::scalar("b")).unwrap());
+        assert!(!contains(&array, &LqValue::scalar("c")).unwrap());
+        assert!(contains(&object, &LqValue::scalar("key")).unwrap());
+        assert!(contains(&LqValue::Nil, &LqValue::scalar("a")).is_err());
+        assert!(starts_with(&LqValue::scalar("hello"), &LqValue::scalar("he")).unwrap());
+        assert!(ends_with(&LqValue::scalar("hello"), &LqValue::scalar("lo")).unwrap());
+        assert!(starts_with(&array, &LqValue::scalar("a")).unwrap());
+        assert!(!ends_with(&array, &LqValue::scalar("a")).unwrap());
+    }
+
+    #[test]
+    fn test_length_and_types() {
+        let array = LqValue::Array(vec![LqValue::scalar(1), LqValue::scalar(2)]);
+
+        assert_eq!(length(&LqValue::scalar("héllo")).unwrap(), 5);
+        assert_eq!(length(&array).unwrap(), 2);
+        assert!(length(&LqValue::scalar(42)).is_err());
+        assert!(is_type(&LqValue::scalar(42), &ValueType::Integer));
+        assert!(is_type(&LqValue::scalar(42), &ValueType::Number));
+        assert!(!is_type(&LqValue::scalar(42), &ValueType::Float));
+        assert!(is_type(&LqValue::scalar(4.2), &ValueType::Float));
+        assert!(is_type(&LqValue::scalar("42"), &ValueType::String));
+        assert!(is_type(&LqValue::scalar(true), &ValueType::Boolean));
+        assert!(is_type(&array, &ValueType::Array));
+        assert!(is_type(&LqValue::Nil, &ValueType::Nil));
+    }
+}
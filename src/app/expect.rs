@@ -0,0 +1,190 @@
+use crate::configuration::manifest::{Expect, HeaderExpectation, StatusExpectation};
+use hyper::{HeaderMap, StatusCode};
+use std::time::Duration;
+
+/// Checks response status, headers and latency against expectations.
+/// Returns result of every check with its message, custom message
+/// of expectation replaces generated one for failed checks.
+pub fn check(
+    expect: &Expect,
+    status: StatusCode,
+    headers: &HeaderMap,
+    elapsed: Duration,
+) -> Vec<(bool, String)> {
+    let mut results = Vec::new();
+    if let Some(expected) = &expect.status {
+        results.push(check_status(expected, status));
+    }
+    let mut names: Vec<&String> = expect.headers.keys().collect();
+    names.sort();
+    for name in names {
+        results.push(check_header(name, &expect.headers[name], headers));
+    }
+    if let Some(latency) = expect.latency {
+        let elapsed = elapsed.as_millis();
+        results.push((
+            elapsed <= u128::from(latency),
+            format!("Latency {} ms must be under {} ms", elapsed, latency),
+        ));
+    }
+    if let Some(message) = &expect.message {
+        for (passed, result_message) in results.iter_mut() {
+            if !*passed {
+                *result_message = message.clone();
+            }
+        }
+    }
+    results
+}
+
+fn check_status(expected: &StatusExpectation, status: StatusCode) -> (bool, String) {
+    let code = status.as_u16();
+    match expected {
+        StatusExpectation::Code(expected) => (
+            code == *expected,
+            format!("Status {} must be equal to {}", code, expected),
+        ),
+        StatusExpectation::Set(expected) => (
+            expected.contains(&code),
+            format!("Status {} must be one of {:?}", code, expected),
+        ),
+        StatusExpectation::Range { from, to } => (
+            (*from..=*to).contains(&code),
+            format!("Status {} must be in range {}..{}", code, from, to),
+        ),
+        StatusExpectation::Class(class) => (
+            code / 100 == *class,
+            format!("Status {} must be {}xx", code, class),
+        ),
+    }
+}
+
+fn check_header(name: &str, expected: &HeaderExpectation, headers: &HeaderMap) -> (bool, String) {
+    let value = headers
+        .get(name)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+    match (expected, value) {
+        (HeaderExpectation::Present { present }, value) => (
+            value.is_some() == *present,
+            if *present {
+                format!("Header '{}' must be present", name)
+            } else {
+                format!("Header '{}' must not be present", name)
+            },
+        ),
+        (HeaderExpectation::Equal(expected), Some(value)) => (
+            &value == expected,
+            format!(
+                "Header '{}' value '{}' must be equal to '{}'",
+                name, value, expected
+            ),
+        ),
+        (HeaderExpectation::Matches { matches }, Some(value)) => (
+            matches.is_match(&value),
+            format!(
+                "Header '{}' value '{}' must match '{}'",
+                name, value, matches
+            ),
+        ),
+        (_, None) => (false, format!("Header '{}' is missing", name)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use hyper::header::{HeaderValue, CONTENT_TYPE, LOCATION};
+    use serde_json::json;
+
+    fn expect(value: serde_json::Value) -> Expect {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn passed(results: Vec<(bool, String)>) -> Vec<bool> {
+        results.into_iter().map(|(passed, _)| passed).collect()
+    }
+
+    #[test]
+    fn test_status_expectations() {
+        let headers = HeaderMap::new();
+        let elapsed = Duration::from_millis(1);
+        let check_status = |value: serde_json::Value, status: u16| {
+            let expect = expect(json!({ "status": value }));
+            let status = StatusCode::from_u16(status).unwrap();
+            passed(check(&expect, status, &headers, elapsed))
+        };
+
+        assert_eq!(check_status(json!(200), 200), vec![true]);
+        assert_eq!(check_status(json!(200), 201), vec![false]);
+        assert_eq!(check_status(json!([200, 204]), 204), vec![true]);
+        assert_eq!(check_status(json!([200, 204]), 201), vec![false]);
+        assert_eq!(
+            check_status(json!({ "from": 200, "to": 299 }), 299),
+            vec![true]
+        );
+        assert_eq!(
+            check_status(json!({ "from": 200, "to": 299 }), 300),
+            vec![false]
+        );
+        assert_eq!(check_status(json!("2xx"), 204), vec![true]);
+        assert_eq!(check_status(json!("4XX"), 500), vec![false]);
+        assert!(serde_json::from_value::<Expect>(json!({ "status": "2xy" })).is_err());
+    }
+
+    #[test]
+    fn test_header_expectations() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(LOCATION, HeaderValue::from_static("/items/42"));
+        let expect = expect(json!({
+            "headers": {
+                "Content-Type": "application/json",
+                "location": { "matches": "^/items/\\d+$" },
+                "X-Request-Id": { "present": true },
+                "X-Debug": { "present": false },
+                "Set-Cookie": "session"
+            }
+        }));
+        let results = check(&expect, StatusCode::OK, &headers, Duration::from_millis(1));
+
+        assert_eq!(
+            results,
+            vec![
+                (true, "Header 'Content-Type' value 'application/json' must be equal to 'application/json'".to_string()),
+                (false, "Header 'Set-Cookie' is missing".to_string()),
+                (true, "Header 'X-Debug' must not be present".to_string()),
+                (false, "Header 'X-Request-Id' must be present".to_string()),
+                (true, "Header 'location' value '/items/42' must match '^/items/\\d+$'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_latency_expectation_and_custom_message() {
+        let expect = expect(json!({ "latency": 100, "status": 200, "message": "Too slow" }));
+        let headers = HeaderMap::new();
+
+        assert_eq!(
+            check(
+                &expect,
+                StatusCode::OK,
+                &headers,
+                Duration::from_millis(150)
+            ),
+            vec![
+                (true, "Status 200 must be equal to 200".to_string()),
+                (false, "Too slow".to_string())
+            ]
+        );
+        assert_eq!(
+            passed(check(
+                &expect,
+                StatusCode::OK,
+                &headers,
+                Duration::from_millis(50)
+            )),
+            vec![true, true]
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Selects pipeline steps to run by tags and name
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    /// Step is selected when it has any of tags, every step is selected when there are no tags
+    pub tags: HashSet<String>,
+    /// Step with any of these tags is filtered out
+    pub skip_tags: HashSet<String>,
+    /// Step is selected when its name matches
+    pub grep: Option<Regex>,
+}
+
+impl Filter {
+    pub fn selects(&self, name: &str, tags: &HashSet<String>) -> bool {
+        (self.tags.is_empty() || !self.tags.is_disjoint(tags))
+            && self.skip_tags.is_disjoint(tags)
+            && self.grep.as_ref().is_none_or(|grep| grep.is_match(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn tags(tags: &[&str]) -> HashSet<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn test_steps_selected_by_tags_and_name() {
+        let filter = Filter {
+            tags: tags(&["smoke", "auth"]),
+            skip_tags: tags(&["slow"]),
+            grep: Some(Regex::new("^Get").unwrap()),
+        };
+
+        assert!(Filter::default().selects("Any", &tags(&[])));
+        assert!(filter.selects("Get user", &tags(&["smoke"])));
+        assert!(!filter.selects("Get user", &tags(&[])));
+        assert!(!filter.selects("Get user", &tags(&["auth", "slow"])));
+        assert!(!filter.selects("Create user", &tags(&["smoke"])));
+    }
+}
@@ -0,0 +1,78 @@
+use super::HookContext;
+use rquickjs::{CatchResultExt, Context, Runtime};
+
+const PRELUDE: &str = r#"
+var ctx = JSON.parse(__context);
+ctx.assert = function (condition, message) {
+    if (!condition) {
+        ctx.failures.push(message === undefined ? "Assertion failed" : String(message));
+    }
+};
+"#;
+
+pub fn execute(code: &str, context: &HookContext) -> Result<HookContext, String> {
+    let input = serde_json::to_string(context).map_err(|e| e.to_string())?;
+    let runtime = Runtime::new().map_err(|e| e.to_string())?;
+    let js = Context::full(&runtime).map_err(|e| e.to_string())?;
+    let output = js.with(|ctx| {
+        ctx.globals()
+            .set("__context", input)
+            .catch(&ctx)
+            .and_then(|_| ctx.eval::<(), _>(PRELUDE).catch(&ctx))
+            .and_then(|_| ctx.eval::<(), _>(code).catch(&ctx))
+            .and_then(|_| ctx.eval::<String, _>("JSON.stringify(ctx)").catch(&ctx))
+            .map_err(|e| e.to_string())
+    })?;
+    serde_json::from_str(&output).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::app::hooks::HookRequest;
+    use serde_json::json;
+
+    #[test]
+    fn test_script_changes_request_and_variables() {
+        let context = HookContext {
+            request: Some(HookRequest {
+                method: "GET".into(),
+                uri: "http://localhost/".into(),
+                ..HookRequest::default()
+            }),
+            ..HookContext::default()
+        };
+        let code = r#"
+            "use strict";
+            ctx.request.headers["X-Signature"] = "signed";
+            ctx.request.uri = ctx.request.uri + "path";
+            ctx.vars.token = "abc";
+        "#;
+        let result = execute(code, &context).unwrap();
+        let request = result.request.unwrap();
+
+        assert_eq!(request.uri, "http://localhost/path");
+        assert_eq!(request.headers.get("X-Signature").unwrap(), "signed");
+        assert_eq!(result.vars.get("token"), Some(&json!("abc")));
+    }
+
+    #[test]
+    fn test_failed_assertions_are_collected() {
+        let code = r#"
+            ctx.assert(true, "never");
+            ctx.assert(false, "always");
+            ctx.assert(1 === 2);
+        "#;
+        let result = execute(code, &HookContext::default()).unwrap();
+
+        assert_eq!(result.failures, vec!["always", "Assertion failed"]);
+    }
+
+    #[test]
+    fn test_script_error_is_reported() {
+        let result = execute("throw new Error('boom')", &HookContext::default());
+
+        assert!(result.unwrap_err().contains("boom"));
+    }
+}
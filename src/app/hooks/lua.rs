@@ -0,0 +1,73 @@
+use super::HookContext;
+use mlua::{Lua, LuaSerdeExt, Value};
+
+const PRELUDE: &str = r#"
+ctx.assert = function(condition, message)
+    if not condition then
+        table.insert(ctx.failures, message == nil and "Assertion failed" or tostring(message))
+    end
+end
+"#;
+
+pub fn execute(code: &str, context: &HookContext) -> Result<HookContext, String> {
+    let lua = Lua::new();
+    let input = lua.to_value(context).map_err(|e| e.to_string())?;
+    lua.globals().set("ctx", input).map_err(|e| e.to_string())?;
+    lua.load(PRELUDE).exec().map_err(|e| e.to_string())?;
+    lua.load(code).exec().map_err(|e| e.to_string())?;
+    lua.load("ctx.assert = nil")
+        .exec()
+        .map_err(|e| e.to_string())?;
+    let output: Value = lua.globals().get("ctx").map_err(|e| e.to_string())?;
+    lua.from_value(output).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::app::hooks::HookRequest;
+    use serde_json::json;
+
+    #[test]
+    fn test_script_changes_request_and_variables() {
+        let context = HookContext {
+            request: Some(HookRequest {
+                method: "GET".into(),
+                uri: "http://localhost/".into(),
+                ..HookRequest::default()
+            }),
+            ..HookContext::default()
+        };
+        let code = r#"
+            ctx.request.headers["X-Signature"] = "signed"
+            ctx.request.uri = ctx.request.uri .. "path"
+            ctx.vars.token = "abc"
+        "#;
+        let result = execute(code, &context).unwrap();
+        let request = result.request.unwrap();
+
+        assert_eq!(request.uri, "http://localhost/path");
+        assert_eq!(request.headers.get("X-Signature").unwrap(), "signed");
+        assert_eq!(result.vars.get("token"), Some(&json!("abc")));
+    }
+
+    #[test]
+    fn test_failed_assertions_are_collected() {
+        let code = r#"
+            ctx.assert(true, "never")
+            ctx.assert(false, "always")
+            ctx.assert(1 == 2)
+        "#;
+        let result = execute(code, &HookContext::default()).unwrap();
+
+        assert_eq!(result.failures, vec!["always", "Assertion failed"]);
+    }
+
+    #[test]
+    fn test_script_error_is_reported() {
+        let result = execute("error('boom')", &HookContext::default());
+
+        assert!(result.unwrap_err().contains("boom"));
+    }
+}
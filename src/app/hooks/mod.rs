@@ -0,0 +1,60 @@
+use crate::app::read_uri;
+use crate::configuration::manifest::{Code, SourceCode};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+mod js;
+mod lua;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HookRequest {
+    pub method: String,
+    pub uri: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HookResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub json: Value,
+}
+
+/// State shared with hook scripts as `ctx` object.
+/// Script may change request (in `before` hooks) and variables,
+/// failed `ctx.assert` calls are collected into `failures`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HookContext {
+    #[serde(default)]
+    pub request: Option<HookRequest>,
+    #[serde(default)]
+    pub response: Option<HookResponse>,
+    #[serde(default)]
+    pub vars: Map<String, Value>,
+    #[serde(default)]
+    pub failures: Vec<String>,
+}
+
+pub fn execute(code: &Code, context: &HookContext) -> Result<HookContext, String> {
+    match code {
+        Code::Js(source) => js::execute(&load_source(source)?, context),
+        Code::Lua(source) => lua::execute(&load_source(source)?, context),
+    }
+}
+
+fn load_source(source: &SourceCode) -> Result<String, String> {
+    match source {
+        SourceCode::Code(code) => Ok(code.clone()),
+        SourceCode::Uri(uri) => read_uri(uri)
+            .ok_or_else(|| format!("Cannot load hook source from {}", uri))
+            .and_then(|data| String::from_utf8(data).map_err(|e| e.to_string())),
+    }
+}
@@ -1,179 +1,855 @@
+use crate::configuration::manifest::quoted;
 use crate::configuration::manifest::AssertFunction;
 use crate::configuration::manifest::AssertParamValueVar;
 use crate::configuration::manifest::BodyEntry;
+use crate::configuration::manifest::Code;
 use crate::configuration::manifest::Functor;
+use crate::configuration::manifest::LengthExpectation;
 use crate::configuration::manifest::Manifest;
-use crate::configuration::manifest::{Capture, CaptureEntry};
+use crate::configuration::manifest::ManifestError;
+use crate::configuration::manifest::PipelineEntry;
+use crate::configuration::manifest::{Capture, CaptureEntry, CaptureSource};
+use crate::configuration::manifest::{MatchMode, Matcher};
+use hooks::{HookContext, HookRequest, HookResponse};
 use hyper::body::to_bytes;
 use hyper::client::HttpConnector;
+use hyper::header::SET_COOKIE;
 use hyper::Client;
 use hyper::Uri;
-use hyper::{Body, Request};
+use hyper::{Body, HeaderMap, Request, Response, StatusCode};
 use hyper_tls::HttpsConnector;
-use kstring::KString;
+use jmespath::Expression;
 use liquid::Object;
-use liquid::Parser;
+use liquid::Template;
+use regex::Regex;
+use report::{RunReport, Status, StepResult};
 use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Instant;
+use sxd_xpath::XPath;
+use template::Random;
 
 use liquid::model::Value as LqValue;
+use liquid::ValueView;
+
+mod assert;
+mod expect;
+mod filter;
+mod hooks;
+pub mod report;
+mod resources;
+mod schema;
+mod secrets;
+mod snapshot;
+mod template;
+
+pub use self::filter::Filter;
+pub use self::secrets::Secrets;
+pub use self::snapshot::Snapshots;
 
 pub struct App {
     manifest: Manifest,
     client: Client<HttpsConnector<HttpConnector>>,
-    parser: Parser,
     globals: Arc<Mutex<Object>>,
+    snapshots: Snapshots,
+    random: Random,
+    env: Object,
+    secrets: Secrets,
+    strict: bool,
+    filter: Filter,
 }
 
 impl App {
-    pub fn new(manifest: Manifest) -> Self {
+    /// Creates app of manifest, templates and schemas of pipeline are compiled once here
+    pub fn new(mut manifest: Manifest) -> Self {
         let client = Client::builder().build::<_, hyper::Body>(HttpsConnector::new());
-        let parser = liquid::ParserBuilder::with_stdlib().build().unwrap();
+        let random = Random::default();
+        let parser = template::parser(&random, manifest.locale);
+        for entry in &mut manifest.pipeline.test {
+            let body =
+                App::unwrap_body_entry(&entry.body).and_then(|body| String::from_utf8(body).ok());
+            entry.templates = template::compile(&parser, entry, body.as_deref());
+            entry.validator = entry.schema.as_ref().map(schema::compile);
+        }
         App {
+            strict: manifest.strict,
             manifest,
             client,
-            parser,
             globals: Arc::default(),
+            snapshots: Snapshots::default(),
+            random,
+            env: Object::new(),
+            secrets: Secrets::default(),
+            filter: Filter::default(),
         }
     }
 
-    pub async fn run(&self) {
-        info!("Starting pipeline '{}'", self.manifest.name);
+    /// Sets environment variables available in templates as `env`
+    pub fn with_env(mut self, env: Object) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Undefined variable in template fails the step instead of being rendered as empty
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Runs only steps selected by filter
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Seeds random generator of templates, so fake and random data can be reproduced
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.random.seed(seed);
+        self
+    }
+
+    /// Shares registry of secret values, so output written outside of the app is masked too
+    pub fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Sets initial global variables, e.g. captured by setup manifests of suite
+    pub fn with_globals(self, globals: Object) -> Self {
+        self.globals.lock().unwrap().extend(globals);
+        self
+    }
+
+    /// Returns global variables captured so far
+    pub fn globals(&self) -> Object {
+        self.globals.lock().unwrap().clone()
+    }
+
+    pub fn with_snapshots(mut self, snapshots: Snapshots) -> Self {
+        self.snapshots = snapshots;
+        self
+    }
+
+    /// Returns problem of each template and schema of pipeline which failed to compile
+    pub fn validate(&self) -> Vec<ManifestError> {
+        let mut problems = Vec::new();
+        for (index, entry) in self.manifest.pipeline.test.iter().enumerate() {
+            let path = format!("pipeline.test[{}]", index);
+            let templates = &entry.templates;
+            let mut compiled = vec![(format!("{}.request", path), &templates.request)];
+            for (key, template) in &templates.headers {
+                compiled.push((format!("{}.headers.{}", path, key), template));
+            }
+            if let Some(template) = &templates.body {
+                compiled.push((format!("{}.body", path), template));
+            }
+            for (path, template) in compiled {
+                if let Err(e) = template {
+                    problems.push((path, format!("Invalid template: {}", e)));
+                }
+            }
+            if let Some(Err(e)) = &entry.validator {
+                problems.push((format!("{}.schema", path), e.clone()));
+            }
+        }
+        problems
+            .into_iter()
+            .map(|(path, message)| self.manifest.error(path, message))
+            .collect()
+    }
+
+    pub async fn run(&self) -> RunReport {
+        // Secrets of every scope are registered before manifest is dumped
+        self.register_secrets(&self.manifest.vars);
         for entry in &self.manifest.pipeline.test {
-            info!("Test {}", entry.name);
-            let mut request = Request::builder()
-                .uri(entry.generate_request_uri())
-                .method(&entry.method);
-            for (key, value) in &entry.headers {
-                request = request.header(
-                    key,
-                    self.apply_body_template(value.to_string(), &entry.vars),
-                );
-            }
-            let prepared;
-            if let Some(body_data) = App::unwrap_body_entry(&entry.body) {
-                let body = match String::from_utf8(body_data.clone()) {
-                    Ok(body) => Body::from(self.apply_body_template(body, &entry.vars)),
-                    Err(_) => Body::from(body_data),
+            self.register_secrets(&entry.vars);
+        }
+        debug!("Initiated configuration {:#?}", self.manifest);
+        let mut report = RunReport::new(&self.manifest.name);
+        let entries = self.selected();
+        if entries.is_empty() {
+            info!("No test of pipeline '{}' is selected", self.manifest.name);
+            return report;
+        }
+        self.check_dependencies(&entries);
+        info!("Starting pipeline '{}'", self.manifest.name);
+        let started = Instant::now();
+        let mut pipeline_rows = Vec::new();
+        let mut step_rows: HashMap<&str, Vec<Object>> = HashMap::new();
+        let mut failed_steps = HashSet::new();
+        let mut pipeline_failed = false;
+        for resource in &self.manifest.resources {
+            match resources::load(resource) {
+                Ok(rows) => match &resource.step {
+                    Some(step) => step_rows.entry(step.as_str()).or_default().extend(rows),
+                    None => pipeline_rows.extend(rows),
+                },
+                Err(e) => {
+                    let mut result = StepResult::new(&resource.uri.to_string());
+                    result.error(format!("Failed to load resource {}", e));
+                    report.steps.push(result);
+                    // Rows of failed resource are unknown, so its steps can't run without them
+                    match &resource.step {
+                        Some(step) => {
+                            failed_steps.insert(step.as_str());
+                        }
+                        None => pipeline_failed = true,
+                    }
+                }
+            }
+        }
+        if pipeline_failed {
+            error!(
+                "Pipeline '{}' is skipped because its resources failed to load",
+                self.manifest.name
+            );
+            report.elapsed = started.elapsed();
+            report.redact(&self.secrets);
+            return report;
+        }
+        if let Some(before_all) = &self.manifest.pipeline.before_all {
+            let mut result = StepResult::new("before_all");
+            self.run_hook(
+                before_all,
+                &Object::new(),
+                &HookContext::default(),
+                &mut result,
+            );
+            report.steps.push(result);
+        }
+        let iterations = pipeline_rows.len();
+        if pipeline_rows.is_empty() {
+            pipeline_rows.push(Object::new());
+        }
+        let empty_row = Object::new();
+        for (iteration, row) in pipeline_rows.iter().enumerate() {
+            for entry in &entries {
+                if failed_steps.contains(entry.name.as_str()) {
+                    warn!(
+                        "Test '{}' is skipped because its resource failed to load",
+                        entry.name
+                    );
+                    continue;
+                }
+                let rows = match step_rows.get(entry.name.as_str()) {
+                    Some(rows) => rows.iter().enumerate().collect(),
+                    None => vec![(0, &empty_row)],
                 };
-                prepared = request.body(body).expect("Cannot create request");
-            } else {
-                prepared = request.body(Body::empty()).expect("Cannot create request");
-            }
-            let now = Instant::now();
-            match self.client.request(prepared).await {
-                Ok(mut response) => {
-                    let body = to_bytes(response.body_mut()).await.unwrap();
-                    debug!(
-                        "Received response {:#?} body {:#?} in {} ms",
-                        response,
-                        body,
-                        now.elapsed().as_millis()
+                for (index, step_row) in rows {
+                    let mut name = entry.name.clone();
+                    if iterations > 0 {
+                        name.push_str(&format!(" #{}", iteration + 1));
+                    }
+                    if step_rows.contains_key(entry.name.as_str()) {
+                        name.push_str(&format!(" #{}", index + 1));
+                    }
+                    let mut vars = row.clone();
+                    vars.extend(step_row.clone());
+                    info!("Test {}", name);
+                    let result = self.run_step(entry, &name, &vars).await;
+                    match result.status() {
+                        Status::Passed => info!(
+                            "Test '{}' passed in {} ms",
+                            name,
+                            result.elapsed.as_millis()
+                        ),
+                        Status::Failed => warn!("Test '{}' failed", name),
+                        Status::Error => error!("Test '{}' finished with error", name),
+                    }
+                    report.steps.push(result);
+                }
+            }
+        }
+        if let Some(after_all) = &self.manifest.pipeline.after_all {
+            let mut result = StepResult::new("after_all");
+            self.run_hook(
+                after_all,
+                &Object::new(),
+                &HookContext::default(),
+                &mut result,
+            );
+            report.steps.push(result);
+        }
+        report.elapsed = started.elapsed();
+        report.redact(&self.secrets);
+        info!(
+            "Finished pipeline '{}' in {} ms: {} passed, {} failed, {} errors",
+            report.name,
+            report.elapsed.as_millis(),
+            report.count(Status::Passed),
+            report.count(Status::Failed),
+            report.count(Status::Error)
+        );
+        report
+    }
+
+    /// Returns steps selected by filter, tags of manifest are tags of each of its steps
+    fn selected(&self) -> Vec<&PipelineEntry> {
+        self.manifest
+            .pipeline
+            .test
+            .iter()
+            .filter(|entry| {
+                let tags = entry.tags.union(&self.manifest.tags).cloned().collect();
+                let selected = self.filter.selects(&entry.name, &tags);
+                if !selected {
+                    debug!("Test '{}' is filtered out", entry.name);
+                }
+                selected
+            })
+            .collect()
+    }
+
+    /// Warns when selected step uses variable which is captured only by filtered out steps
+    fn check_dependencies(&self, selected: &[&PipelineEntry]) {
+        let captured: HashSet<&str> = selected
+            .iter()
+            .flat_map(|entry| entry.capture.iter().map(|cap| cap.variable.as_str()))
+            .collect();
+        let missing: Vec<(&PipelineEntry, &str)> = self
+            .manifest
+            .pipeline
+            .test
+            .iter()
+            .filter(|entry| {
+                !selected
+                    .iter()
+                    .any(|selected| std::ptr::eq(*selected, *entry))
+            })
+            .flat_map(|entry| {
+                entry
+                    .capture
+                    .iter()
+                    .map(move |cap| (entry, cap.variable.as_str()))
+            })
+            .filter(|(_, variable)| !captured.contains(variable))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        for consumer in selected {
+            let variables = App::variables(consumer);
+            for (producer, variable) in &missing {
+                if variables.contains(*variable) {
+                    warn!(
+                        "Test '{}' uses variable '{}' captured by test '{}' which is filtered out",
+                        consumer.name, variable, producer.name
                     );
-                    self.capture_body(&body, &entry.capture);
                 }
-                Err(e) => error!("Failed to send request {}", e),
             }
         }
     }
 
-    fn capture_body(&self, body: &[u8], capture: &[CaptureEntry]) -> Object {
-        let body_string = match String::from_utf8(body.to_vec()) {
-            Ok(s) => s,
+    /// Returns names of variables which templates and assertions of step may use
+    fn variables(entry: &PipelineEntry) -> HashSet<String> {
+        let body = App::unwrap_body_entry(&entry.body)
+            .and_then(|body| String::from_utf8(body).ok())
+            .unwrap_or_default();
+        let mut variables = template::variables(&entry.request);
+        for source in entry.headers.values().chain(Some(&body)) {
+            variables.extend(template::variables(source));
+        }
+        let functors = entry.capture.iter().flat_map(|cap| cap.on.iter());
+        variables.extend(functors.flat_map(Functor::variables).map(String::from));
+        variables
+    }
+
+    /// Executes single pipeline step, data row columns override step variables.
+    async fn run_step(&self, entry: &PipelineEntry, name: &str, row: &Object) -> StepResult {
+        let mut result = StepResult::new(name);
+        let mut vars = entry.vars.clone();
+        vars.extend(row.clone());
+        let context = self.context(&vars);
+        self.register_secrets(&context);
+        let templates = &entry.templates;
+        let rendered = self.render(&templates.request, &context).and_then(|uri| {
+            let headers = templates
+                .headers
+                .iter()
+                .map(|(key, template)| {
+                    self.render(template, &context)
+                        .map(|value| (key.clone(), value))
+                        .map_err(|e| format!("header '{}': {}", key, e))
+                })
+                .collect::<Result<_, _>>()?;
+            let body = match &templates.body {
+                Some(template) => Some(
+                    self.render(template, &context)
+                        .map_err(|e| format!("body: {}", e))?,
+                ),
+                None => None,
+            };
+            Ok((uri, headers, body))
+        });
+        let (uri, headers, body) = match rendered {
+            Ok(rendered) => rendered,
             Err(e) => {
-                error!("Failed to parse body string: {}", e);
-                return Object::default();
+                result.error(format!("Failed to render request {}", e));
+                return result;
             }
         };
-        let mut result = Object::new();
-        for cap in capture {
-            let value = match &cap.cap {
-                Capture::Json(selector) => {
-                    let data: Value = serde_json::from_str(&body_string)
-                        .expect("Cannot serialize object to json");
-                    let captured: Vec<Value> = selector.find(&data).cloned().collect();
-                    if captured.len() == 1 {
-                        captured[0].into_liquid()
-                    } else if !captured.is_empty() {
-                        captured.into_liquid()
-                    } else {
-                        LqValue::Nil
+        let binary_body = match body {
+            Some(_) => None,
+            None => App::unwrap_body_entry(&entry.body),
+        };
+        let mut request = HookRequest {
+            method: entry.method.to_string(),
+            uri,
+            headers,
+            body,
+        };
+        if let Some(before) = &entry.before {
+            let hook_context = HookContext {
+                request: Some(request.clone()),
+                ..HookContext::default()
+            };
+            let hook_result = self.run_hook(before, &vars, &hook_context, &mut result);
+            if let Some(changed) = hook_result.request {
+                request = changed;
+            }
+        }
+        let body = match (&request.body, binary_body) {
+            (Some(body), _) => Body::from(body.clone()),
+            (None, Some(body_data)) => Body::from(body_data),
+            (None, None) => Body::empty(),
+        };
+        let mut builder = Request::builder()
+            .uri(request.uri.as_str())
+            .method(request.method.as_str());
+        for (key, value) in &request.headers {
+            builder = builder.header(key.as_str(), value.as_str());
+        }
+        let prepared = match builder.body(body) {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                result.error(format!("Cannot create request {}", e));
+                return result;
+            }
+        };
+        result.request = Some(summarize_request(&request));
+        let now = Instant::now();
+        let mut response = match self.client.request(prepared).await {
+            Ok(response) => response,
+            Err(e) => {
+                result.elapsed = now.elapsed();
+                result.error(format!("Failed to send request {}", e));
+                return result;
+            }
+        };
+        let body = match to_bytes(response.body_mut()).await {
+            Ok(body) => body,
+            Err(e) => {
+                result.elapsed = now.elapsed();
+                result.error(format!("Failed to receive response body {}", e));
+                return result;
+            }
+        };
+        result.elapsed = now.elapsed();
+        result.response = Some(summarize_response(&response, &body));
+        if let Some(expect) = &entry.expect {
+            let checks = expect::check(
+                expect,
+                response.status(),
+                response.headers(),
+                result.elapsed,
+            );
+            for (passed, message) in checks {
+                result.assert(passed, message);
+            }
+        }
+        match &entry.validator {
+            Some(Ok(validator)) => {
+                for (passed, message) in schema::check(validator, &body) {
+                    result.assert(passed, message);
+                }
+            }
+            Some(Err(e)) => result.error(e.clone()),
+            None => {}
+        }
+        let captured = self.capture(
+            response.status(),
+            response.headers(),
+            &body,
+            &entry.capture,
+            &self.context(&vars),
+            &mut result,
+        );
+        // Response is logged after capture, so captured secrets are already masked
+        debug!(
+            "Received response {:#?} body {:#?} in {} ms",
+            response,
+            body,
+            result.elapsed.as_millis()
+        );
+        // Snapshot is checked after capture, so captured secrets are already masked in stored copy
+        if let Some(snapshot) = &entry.snapshot {
+            match self.snapshots.check(
+                name,
+                snapshot,
+                response.status(),
+                response.headers(),
+                &body,
+                &self.secrets,
+            ) {
+                Ok(checks) => {
+                    for (passed, message) in checks {
+                        result.assert(passed, message);
                     }
                 }
-                Capture::Regex(_) => unimplemented!(),
+                Err(e) => result.error(e),
+            }
+        }
+        result.captured = captured.clone();
+        self.globals.lock().unwrap().extend(captured);
+        if let Some(after) = &entry.after {
+            let hook_context = HookContext {
+                request: Some(request),
+                response: Some(HookResponse {
+                    status: response.status().as_u16(),
+                    headers: response
+                        .headers()
+                        .iter()
+                        .map(|(key, value)| {
+                            let value = String::from_utf8_lossy(value.as_bytes());
+                            (key.to_string(), value.into_owned())
+                        })
+                        .collect(),
+                    body: String::from_utf8_lossy(&body).into_owned(),
+                    json: serde_json::from_slice(&body).unwrap_or_default(),
+                }),
+                ..HookContext::default()
             };
-            let mut assert_result;
-            for assertion in &cap.on {
-                match assertion {
-                    Functor::Assert { function, message } => {
-                        assert_result = self.assert_value(&value, function);
-                        trace!("Assert result {}", assert_result);
-                        if !assert_result {
-                            if let Some(message) = message {
-                                info!("Assertation failed: {}", message);
+            self.run_hook(after, &vars, &hook_context, &mut result);
+        }
+        result
+    }
+
+    /// Executes hook script with variables of the step exposed as `ctx.vars`.
+    /// Variables created or changed by the script are stored into globals.
+    fn run_hook(
+        &self,
+        code: &Code,
+        vars: &Object,
+        hook_context: &HookContext,
+        result: &mut StepResult,
+    ) -> HookContext {
+        let mut hook_context = hook_context.clone();
+        if let Ok(Value::Object(vars)) = serde_json::to_value(self.context(vars)) {
+            hook_context.vars = vars;
+        }
+        match hooks::execute(code, &hook_context) {
+            Ok(hook_result) => {
+                for failure in &hook_result.failures {
+                    result.assert(false, failure.clone());
+                }
+                let mut globals = self.globals.lock().unwrap();
+                for (key, value) in &hook_result.vars {
+                    if hook_context.vars.get(key) != Some(value) {
+                        globals.insert(key.clone().into(), value.into_liquid());
+                    }
+                }
+                self.register_secrets(&globals);
+                drop(globals);
+                hook_result
+            }
+            Err(e) => {
+                result.error(format!("Failed to execute hook {}", e));
+                hook_context
+            }
+        }
+    }
+
+    fn capture(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+        capture: &[CaptureEntry],
+        context: &Object,
+        result: &mut StepResult,
+    ) -> Object {
+        let body_string = String::from_utf8_lossy(body);
+        let mut captured = Object::new();
+        for cap in capture {
+            let source = capture_source(&cap.from, status, headers, &body_string);
+            let text = match (&cap.cap, &source) {
+                (None, _) | (_, LqValue::Nil) => None,
+                (Some(_), LqValue::Object(_)) => serde_json::to_string(&source).ok(),
+                (Some(_), source) => Some(source.to_kstr().into_string()),
+            };
+            let value = match (&cap.cap, text) {
+                (None, _) => source,
+                (Some(_), None) => LqValue::Nil,
+                (Some(Capture::Json(selector)), Some(text)) => {
+                    match serde_json::from_str::<Value>(&text) {
+                        Ok(data) => {
+                            let found: Vec<Value> =
+                                selector.query(&data).all().into_iter().cloned().collect();
+                            if found.len() == 1 {
+                                found[0].into_liquid()
+                            } else if !found.is_empty() {
+                                found.into_liquid()
+                            } else {
+                                LqValue::Nil
                             }
                         }
+                        Err(e) => {
+                            result.assert(
+                                false,
+                                format!(
+                                    "Cannot capture '{}', {} is not json: {}",
+                                    cap.variable, cap.from, e
+                                ),
+                            );
+                            continue;
+                        }
                     }
-                    Functor::Matches(_pattern) => {
-                        unimplemented!();
+                }
+                (Some(Capture::Jmespath(expression)), Some(text)) => {
+                    match capture_jmespath(expression, &text) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            result
+                                .assert(false, format!("Cannot capture '{}', {}", cap.variable, e));
+                            continue;
+                        }
                     }
                 }
-                if assert_result {
-                    result.insert(cap.variable.clone().into(), value.clone());
+                (Some(Capture::Regex(regex)), Some(text)) => capture_regex(regex, &text),
+                (Some(Capture::Xpath(xpath)), Some(text)) => {
+                    match capture_xpath(xpath, &cap.namespaces, &text) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            result
+                                .assert(false, format!("Cannot capture '{}', {}", cap.variable, e));
+                            continue;
+                        }
+                    }
                 }
+            };
+            if cap.secret || self.manifest.secrets.contains(&cap.variable) {
+                self.secrets.add_value(&value);
+            }
+            let mut passed = true;
+            for assertion in &cap.on {
+                let (assert_result, message) =
+                    self.check_functor(&cap.variable, &value, assertion, context);
+                result.assert(assert_result, message);
+                passed &= assert_result;
+            }
+            if passed {
+                captured.insert(cap.variable.clone().into(), value);
             }
         }
-        result
+        captured
     }
 
-    fn resolve_assert_parameter(&self, value: &AssertParamValueVar) -> Result<LqValue, String> {
-        trace!("Resolving assert parameter {:?}", value);
-        match value {
-            AssertParamValueVar::Value(object) => Ok(object.clone()),
-            AssertParamValueVar::Var(var_name) => {
-                let key = KString::from(var_name.clone());
-                let lock = self.globals.lock().unwrap();
-                if let Some(value) = (*lock).get(&key) {
-                    return Ok(value.clone());
+    /// Evaluates functor against captured value, combinators are evaluated recursively
+    /// and failure message of combinator shows path to the failed branch.
+    fn check_functor(
+        &self,
+        variable: &str,
+        value: &LqValue,
+        functor: &Functor,
+        context: &Object,
+    ) -> (bool, String) {
+        match functor {
+            Functor::Assert { function, message } => {
+                let assert_result = self.assert_value(value, function, context);
+                let message = message.clone().unwrap_or_else(|| {
+                    format!(
+                        "Value {} of '{}' must {}",
+                        quoted(value),
+                        variable,
+                        function
+                    )
+                });
+                (assert_result, message)
+            }
+            Functor::Matches(matcher) => match match_value(value, matcher) {
+                Ok(()) => (
+                    true,
+                    format!("Value of '{}' matches '{}'", variable, matcher.pattern()),
+                ),
+                Err(message) => (false, message),
+            },
+            Functor::All(functors) => {
+                let failed: Vec<String> = functors
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, functor)| {
+                        match self.check_functor(variable, value, functor, context) {
+                            (true, _) => None,
+                            (false, message) => Some(format!("all[{}]: {}", index, message)),
+                        }
+                    })
+                    .collect();
+                if failed.is_empty() {
+                    (
+                        true,
+                        format!(
+                            "Value of '{}' satisfies all of {} checks",
+                            variable,
+                            functors.len()
+                        ),
+                    )
+                } else {
+                    (false, failed.join("; "))
                 }
-                drop(lock);
-                match self.manifest.vars.get(&key) {
-                    Some(value) => Ok(value.clone()),
-                    None => Err(String::from("Value not found")),
+            }
+            Functor::Any(functors) => {
+                let mut failed = Vec::new();
+                for (index, functor) in functors.iter().enumerate() {
+                    match self.check_functor(variable, value, functor, context) {
+                        (true, message) => return (true, format!("any[{}]: {}", index, message)),
+                        (false, message) => failed.push(format!("any[{}]: {}", index, message)),
+                    }
+                }
+                if failed.is_empty() {
+                    (
+                        false,
+                        format!("Value of '{}' must satisfy any of empty checks", variable),
+                    )
+                } else {
+                    (false, failed.join("; "))
+                }
+            }
+            Functor::Not(functor) => {
+                let (assert_result, message) =
+                    self.check_functor(variable, value, functor, context);
+                match functor.as_ref() {
+                    Functor::Assert {
+                        function,
+                        message: None,
+                    } => (
+                        !assert_result,
+                        format!(
+                            "Value {} of '{}' must not {}",
+                            quoted(value),
+                            variable,
+                            function
+                        ),
+                    ),
+                    _ => (!assert_result, format!("not: {}", message)),
                 }
             }
         }
     }
 
-    fn assert_value(&self, value: &LqValue, assert: &AssertFunction) -> bool {
+    /// Builds the variable context used for rendering templates of a single step.
+    /// Step variables take precedence over captured values, which in turn take
+    /// precedence over manifest variables, `env` and generated `fake` data.
+    fn context(&self, vars: &Object) -> Object {
+        let mut context = Object::new();
+        context.insert("env".into(), LqValue::Object(self.env.clone()));
+        context.insert(
+            "fake".into(),
+            LqValue::Object(template::fake::object(&self.random, self.manifest.locale)),
+        );
+        context.extend(self.manifest.vars.clone());
+        context.extend(self.globals.lock().unwrap().clone());
+        context.extend(vars.clone());
+        context
+    }
+
+    /// Registers values of variables and environment variables named in `secrets` of manifest
+    fn register_secrets(&self, vars: &Object) {
+        for name in &self.manifest.secrets {
+            let values = [vars.get(name.as_str()), self.env.get(name.as_str())];
+            for value in values.iter().flatten() {
+                self.secrets.add_value(value);
+            }
+        }
+    }
+
+    /// Resolves parameter of assertion, variable is looked up in the same context as templates of the step
+    fn resolve_assert_parameter(
+        value: &AssertParamValueVar,
+        context: &Object,
+    ) -> Result<LqValue, String> {
+        trace!("Resolving assert parameter {:?}", value);
+        match value {
+            AssertParamValueVar::Value(object) => Ok(object.clone()),
+            AssertParamValueVar::Var(var_name) => context
+                .get(var_name.as_str())
+                .cloned()
+                .ok_or_else(|| format!("Variable '{}' is not defined", var_name)),
+        }
+    }
+
+    fn assert_value(&self, value: &LqValue, assert: &AssertFunction, context: &Object) -> bool {
         trace!("Assertation value: {:#?} to {:#?}", value, assert);
-        match assert {
-            AssertFunction::Equal(var) => match self.resolve_assert_parameter(var) {
-                Ok(expected) => {
-                    trace!("Check equals of {:?} to {:?}", expected, value);
-                    value == &expected
-                }
-                Err(e) => {
-                    error!("{}", e);
-                    false
-                }
-            },
-            AssertFunction::NotEqual(var) => match self.resolve_assert_parameter(var) {
-                Ok(expected) => value != &expected,
-                Err(e) => {
-                    error!("{}", e);
-                    false
-                }
-            },
+        match self.check_assertion(value, assert, context) {
+            Ok(result) => result,
+            Err(e) => {
+                error!("{}", e);
+                false
+            }
         }
     }
 
-    fn apply_body_template(&self, body: String, values: &Object) -> String {
-        let template = self.parser.parse(body.as_str()).unwrap();
-        template.render(values).unwrap()
+    fn check_assertion(
+        &self,
+        value: &LqValue,
+        assert: &AssertFunction,
+        context: &Object,
+    ) -> Result<bool, String> {
+        let resolve = |param| App::resolve_assert_parameter(param, context);
+        let result = match assert {
+            AssertFunction::Equal(var) => {
+                let expected = resolve(var)?;
+                trace!("Check equals of {:?} to {:?}", expected, value);
+                value == &expected
+            }
+            AssertFunction::NotEqual(var) => value != &resolve(var)?,
+            AssertFunction::Gt(var) => assert::compare(value, &resolve(var)?)? == Ordering::Greater,
+            AssertFunction::Ge(var) => assert::compare(value, &resolve(var)?)? != Ordering::Less,
+            AssertFunction::Lt(var) => assert::compare(value, &resolve(var)?)? == Ordering::Less,
+            AssertFunction::Le(var) => assert::compare(value, &resolve(var)?)? != Ordering::Greater,
+            AssertFunction::Contains(var) => assert::contains(value, &resolve(var)?)?,
+            AssertFunction::StartsWith(var) => assert::starts_with(value, &resolve(var)?)?,
+            AssertFunction::EndsWith(var) => assert::ends_with(value, &resolve(var)?)?,
+            AssertFunction::Length(LengthExpectation::Exact(var)) => {
+                let expected = resolve(var)?;
+                let expected = expected
+                    .as_scalar()
+                    .and_then(|scalar| scalar.to_integer())
+                    .ok_or_else(|| {
+                        format!("Expected length must be integer, got {:?}", expected)
+                    })?;
+                assert::length(value)? as i64 == expected
+            }
+            AssertFunction::Length(LengthExpectation::Range { from, to }) => {
+                (*from..=*to).contains(&assert::length(value)?)
+            }
+            AssertFunction::TypeIs(value_type) => assert::is_type(value, value_type),
+            AssertFunction::Exists(exists) => value.is_nil() != *exists,
+            AssertFunction::IsNil(is_nil) => value.is_nil() == *is_nil,
+            AssertFunction::OneOf(var) => match resolve(var)? {
+                LqValue::Array(options) => options.contains(value),
+                other => return Err(format!("Expected array of options, got {:?}", other)),
+            },
+            AssertFunction::Between { from, to } => {
+                assert::compare(value, &resolve(from)?)? != Ordering::Less
+                    && assert::compare(value, &resolve(to)?)? != Ordering::Greater
+            }
+        };
+        Ok(result)
+    }
+
+    fn render(
+        &self,
+        template: &Result<Template, String>,
+        context: &Object,
+    ) -> Result<String, String> {
+        match template {
+            Ok(template) => template::render(template, context, self.strict),
+            Err(e) => Err(format!("invalid template: {}", e)),
+        }
     }
 
     fn unwrap_body_entry(body_data: &Option<BodyEntry>) -> Option<Vec<u8>> {
@@ -181,13 +857,13 @@ impl App {
             Some(BodyEntry::Raw(body)) => Some(Vec::from(body.as_bytes())),
             Some(BodyEntry::Json(body)) => Some(serde_json::to_vec(body).unwrap()),
             Some(BodyEntry::Uri(body)) => read_uri(body),
-            Some(BodyEntry::Base64(body)) => Some(base64::decode(body).unwrap()),
+            Some(BodyEntry::Base64(body)) => Some(body.clone()),
             None => None,
         }
     }
 }
 
-fn read_uri(uri: &Uri) -> Option<Vec<u8>> {
+pub(crate) fn read_uri(uri: &Uri) -> Option<Vec<u8>> {
     if let Some(scheme) = uri.scheme_str() {
         return match scheme {
             "file" => match fs::read(format!("{}{}", uri.authority().unwrap(), uri.path())) {
@@ -207,7 +883,178 @@ fn read_uri(uri: &Uri) -> Option<Vec<u8>> {
     None
 }
 
+fn summarize_request(request: &HookRequest) -> String {
+    let mut summary = format!("{} {}\n", request.method, request.uri);
+    for (key, value) in &request.headers {
+        summary.push_str(&format!("{}: {}\n", key, value));
+    }
+    if let Some(body) = &request.body {
+        summary.push_str(&format!("\n{}\n", body));
+    }
+    summary
+}
+
+fn summarize_response(response: &Response<Body>, body: &[u8]) -> String {
+    let mut summary = format!("{:?} {}\n", response.version(), response.status());
+    for (key, value) in response.headers() {
+        summary.push_str(&format!(
+            "{}: {}\n",
+            key,
+            String::from_utf8_lossy(value.as_bytes())
+        ));
+    }
+    summary.push_str(&format!("\n{}\n", String::from_utf8_lossy(body)));
+    summary
+}
+
+/// Reads captured source of response, status is captured as number, headers as object
+/// of lowercase names and missing header or cookie as nil. Multiple values are joined with comma.
+fn capture_source(
+    source: &CaptureSource,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+) -> LqValue {
+    let header_value = |name: &str| {
+        let values: Vec<&str> = headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+        if values.is_empty() {
+            LqValue::Nil
+        } else {
+            LqValue::scalar(values.join(", "))
+        }
+    };
+    match source {
+        CaptureSource::Body => LqValue::scalar(body.to_owned()),
+        CaptureSource::Status => LqValue::scalar(i64::from(status.as_u16())),
+        CaptureSource::Headers => LqValue::Object(
+            headers
+                .keys()
+                .map(|name| (name.as_str().to_owned().into(), header_value(name.as_str())))
+                .collect(),
+        ),
+        CaptureSource::Header(name) => header_value(name),
+        CaptureSource::Cookie(name) => headers
+            .get_all(SET_COOKIE)
+            .iter()
+            .rev()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|cookie| {
+                let (key, value) = cookie.split(';').next()?.split_once('=')?;
+                if key.trim() == name {
+                    Some(LqValue::scalar(value.trim().to_owned()))
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(LqValue::Nil),
+    }
+}
+
+fn capture_jmespath(expression: &Expression, text: &str) -> Result<LqValue, String> {
+    let data: Value = serde_json::from_str(text).map_err(|e| format!("not json: {}", e))?;
+    let found = expression.search(data).map_err(|e| e.to_string())?;
+    serde_json::to_value(&*found)
+        .map(|value| value.into_liquid())
+        .map_err(|e| e.to_string())
+}
+
+/// Checks that captured value matches the pattern.
+/// Array values are checked element by element according to the match mode.
+fn match_value(value: &LqValue, matcher: &Matcher) -> Result<(), String> {
+    let pattern = matcher.pattern();
+    let matches = |value: &LqValue| -> Result<(), String> {
+        match value {
+            LqValue::Nil => Err(format!("Expected value matching '{}' but got nil", pattern)),
+            LqValue::Scalar(scalar) if scalar.type_name() == "string" => {
+                let text = scalar.to_kstr();
+                if pattern.is_match(text.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("Value '{}' does not match '{}'", text, pattern))
+                }
+            }
+            other => Err(format!(
+                "Expected string matching '{}' but got {}",
+                pattern,
+                other.type_name()
+            )),
+        }
+    };
+    match (value, matcher.mode()) {
+        (LqValue::Array(array), MatchMode::Every) => array.iter().try_for_each(matches),
+        (LqValue::Array(array), MatchMode::Any) => {
+            if array.iter().any(|value| matches(value).is_ok()) {
+                Ok(())
+            } else {
+                Err(format!("None of array elements match '{}'", pattern))
+            }
+        }
+        (value, _) => matches(value),
+    }
+}
+
+/// Applies regex to the text and converts matches into liquid value.
+/// Pattern with named groups yields an object of groups per match, pattern with
+/// a single group yields the group text, several numbered groups yield an array
+/// of groups and pattern without groups yields the whole match.
+/// Multiple matches are collected into an array, no matches result in nil.
+fn capture_regex(regex: &Regex, text: &str) -> LqValue {
+    let named: Vec<&str> = regex.capture_names().flatten().collect();
+    let mut captured: Vec<LqValue> = regex
+        .captures_iter(text)
+        .map(|captures| {
+            let group = |m: Option<regex::Match>| {
+                m.map(|m| LqValue::scalar(m.as_str().to_string()))
+                    .unwrap_or(LqValue::Nil)
+            };
+            if !named.is_empty() {
+                let mut object = Object::new();
+                for name in &named {
+                    object.insert(name.to_string().into(), group(captures.name(name)));
+                }
+                LqValue::Object(object)
+            } else if captures.len() == 2 {
+                group(captures.get(1))
+            } else if captures.len() > 2 {
+                LqValue::Array(captures.iter().skip(1).map(group).collect())
+            } else {
+                group(captures.get(0))
+            }
+        })
+        .collect();
+    match captured.len() {
+        0 => LqValue::Nil,
+        1 => captured.remove(0),
+        _ => LqValue::Array(captured),
+    }
+}
+
+/// Parses text as XML document and evaluates XPath expression against it.
+/// Namespace prefixes used in the expression are resolved using declared namespaces.
+fn capture_xpath(
+    xpath: &XPath,
+    namespaces: &HashMap<String, String>,
+    text: &str,
+) -> Result<LqValue, String> {
+    let package =
+        sxd_document::parser::parse(text).map_err(|e| format!("body is not xml: {}", e))?;
+    let document = package.as_document();
+    let mut context = sxd_xpath::Context::new();
+    for (prefix, uri) in namespaces {
+        context.set_namespace(prefix, uri);
+    }
+    xpath
+        .evaluate(&context, document.root())
+        .map(|value| value.into_liquid())
+        .map_err(|e| format!("XPath evaluation failed: {}", e))
+}
+
 // TODO: move this to a separate module
+#[allow(clippy::wrong_self_convention)]
 trait IntoLiquid<T> {
     fn into_liquid(&self) -> T;
 }
@@ -234,6 +1081,31 @@ impl IntoLiquid<LqValue> for Value {
     }
 }
 
+impl IntoLiquid<LqValue> for sxd_xpath::Value<'_> {
+    fn into_liquid(&self) -> LqValue {
+        match self {
+            sxd_xpath::Value::Boolean(boolean) => LqValue::scalar(*boolean),
+            sxd_xpath::Value::Number(num) if num.fract() == 0.0 && num.abs() < i64::MAX as f64 => {
+                LqValue::scalar(*num as i64)
+            }
+            sxd_xpath::Value::Number(num) => LqValue::scalar(*num),
+            sxd_xpath::Value::String(string) => LqValue::scalar(string.to_string()),
+            sxd_xpath::Value::Nodeset(nodeset) => {
+                let mut nodes: Vec<LqValue> = nodeset
+                    .document_order()
+                    .iter()
+                    .map(|node| LqValue::scalar(node.string_value()))
+                    .collect();
+                match nodes.len() {
+                    0 => LqValue::Nil,
+                    1 => nodes.remove(0),
+                    _ => LqValue::Array(nodes),
+                }
+            }
+        }
+    }
+}
+
 impl IntoLiquid<LqValue> for Vec<Value> {
     fn into_liquid(&self) -> LqValue {
         LqValue::Array(self.iter().map(Value::into_liquid).collect())
@@ -244,6 +1116,7 @@ impl IntoLiquid<LqValue> for Vec<Value> {
 mod test {
 
     use super::*;
+    use crate::configuration::deserialize::matcher;
     use serde_json::json;
     use std::iter::FromIterator;
 
@@ -255,7 +1128,7 @@ mod test {
         let assert_function = AssertFunction::Equal(AssertParamValueVar::Value(LqValue::Scalar(
             liquid::model::scalar::Scalar::new(42),
         )));
-        let result = app.assert_value(&value, &assert_function);
+        let result = app.assert_value(&value, &assert_function, &app.context(&Object::new()));
 
         assert!(result);
     }
@@ -264,16 +1137,39 @@ mod test {
     fn test_value_equals_to_variable() {
         let value = LqValue::Scalar(liquid::model::scalar::Scalar::new(42));
         let object = Object::from_iter(vec![("expect".into(), value)]);
-        let mut manifest = Manifest::default();
-        manifest.vars = object;
+        let manifest = Manifest {
+            vars: object,
+            ..Manifest::default()
+        };
         let app = App::new(manifest);
         let value = LqValue::Scalar(liquid::model::scalar::Scalar::new(42));
         let assert_function = AssertFunction::Equal(AssertParamValueVar::Var("expect".into()));
-        let result = app.assert_value(&value, &assert_function);
+        let result = app.assert_value(&value, &assert_function, &app.context(&Object::new()));
 
         assert!(result);
     }
 
+    #[test]
+    fn test_value_equals_to_step_variable() {
+        let app = App::new(Manifest::default());
+        let mut vars = Object::new();
+        vars.insert("token".into(), LqValue::scalar("abc"));
+        let assert_function = AssertFunction::Equal(AssertParamValueVar::Var("token".into()));
+
+        assert!(app.assert_value(
+            &LqValue::scalar("abc"),
+            &assert_function,
+            &app.context(&vars)
+        ));
+        assert_eq!(
+            App::resolve_assert_parameter(
+                &AssertParamValueVar::Var("token".into()),
+                &Object::new()
+            ),
+            Err("Variable 'token' is not defined".to_string())
+        );
+    }
+
     #[test]
     fn test_value_not_equals_to_expexted_value() {
         let manifest = Manifest::default();
@@ -282,7 +1178,7 @@ mod test {
         let assert_function = AssertFunction::NotEqual(AssertParamValueVar::Value(
             LqValue::Scalar(liquid::model::scalar::Scalar::new(43)),
         ));
-        let result = app.assert_value(&value, &assert_function);
+        let result = app.assert_value(&value, &assert_function, &app.context(&Object::new()));
 
         assert!(result);
     }
@@ -291,16 +1187,505 @@ mod test {
     fn test_value_not_equals_to_variable() {
         let value = LqValue::Scalar(liquid::model::scalar::Scalar::new(43));
         let object = Object::from_iter(vec![("expect".into(), value)]);
-        let mut manifest = Manifest::default();
-        manifest.vars = object;
+        let manifest = Manifest {
+            vars: object,
+            ..Manifest::default()
+        };
         let app = App::new(manifest);
         let value = LqValue::Scalar(liquid::model::scalar::Scalar::new(42));
         let assert_function = AssertFunction::NotEqual(AssertParamValueVar::Var("expect".into()));
-        let result = app.assert_value(&value, &assert_function);
+        let result = app.assert_value(&value, &assert_function, &app.context(&Object::new()));
 
         assert!(result);
     }
 
+    #[test]
+    fn test_value_satisfies_extended_assertions() {
+        let mut vars = Object::new();
+        vars.insert("min".into(), LqValue::scalar(10));
+        let app = App::new(Manifest {
+            vars,
+            ..Manifest::default()
+        });
+        let check = |value: LqValue, assert: serde_json::Value| {
+            let assert: AssertFunction = serde_json::from_value(assert).unwrap();
+            app.assert_value(&value, &assert, &app.context(&Object::new()))
+        };
+        let list = || LqValue::Array(vec![LqValue::scalar("a"), LqValue::scalar("b")]);
+
+        assert!(check(
+            LqValue::scalar(11),
+            json!({ "gt": { "var": "min" } })
+        ));
+        assert!(!check(
+            LqValue::scalar(10),
+            json!({ "lt": { "var": "min" } })
+        ));
+        assert!(check(LqValue::scalar(10), json!({ "ge": { "value": 10 } })));
+        assert!(check(
+            LqValue::scalar(5),
+            json!({ "between": { "from": { "value": 1 }, "to": { "var": "min" } } })
+        ));
+        assert!(check(
+            LqValue::scalar("token"),
+            json!({ "starts_with": { "value": "to" } })
+        ));
+        assert!(check(list(), json!({ "length": { "value": 2 } })));
+        assert!(check(list(), json!({ "length": { "from": 1, "to": 3 } })));
+        assert!(check(list(), json!({ "type_is": "array" })));
+        assert!(check(
+            LqValue::scalar("b"),
+            json!({ "one_of": { "value": ["a", "b"] } })
+        ));
+        assert!(!check(
+            LqValue::scalar("c"),
+            json!({ "one_of": { "value": ["a", "b"] } })
+        ));
+        assert!(check(LqValue::Nil, json!({ "exists": false })));
+        assert!(check(LqValue::Nil, json!({ "is_nil": true })));
+        assert!(!check(LqValue::Nil, json!({ "gt": { "value": 1 } })));
+    }
+
+    #[test]
+    fn test_combinators_of_functors() {
+        let app = App::new(Manifest::default());
+        let functor: Functor = serde_json::from_value(json!({
+            "any": [
+                { "assert": { "equal": { "value": 200 } } },
+                { "all": [
+                    { "assert": { "ge": { "value": 200 } } },
+                    { "not": { "assert": { "equal": { "value": 204 }, "message": "status is 204" } } }
+                ] }
+            ]
+        }))
+        .unwrap();
+
+        assert!(
+            app.check_functor("status", &LqValue::scalar(200), &functor, &Object::new())
+                .0
+        );
+        assert!(
+            app.check_functor("status", &LqValue::scalar(201), &functor, &Object::new())
+                .0
+        );
+        let (passed, message) =
+            app.check_functor("status", &LqValue::scalar(204), &functor, &Object::new());
+        assert!(!passed);
+        assert!(message.ends_with("any[1]: all[1]: not: status is 204"));
+
+        let functor: Functor = serde_json::from_value(json!({
+            "all": [
+                { "assert": { "equal": { "value": "a" } } },
+                { "not": { "assert": { "starts_with": { "value": "b" } } } },
+                { "assert": { "length": { "from": 2, "to": 3 } } }
+            ]
+        }))
+        .unwrap();
+        let (_, message) =
+            app.check_functor("name", &LqValue::scalar("b"), &functor, &Object::new());
+        assert_eq!(
+            message,
+            "all[0]: Value 'b' of 'name' must equal 'a'; \
+             all[1]: Value 'b' of 'name' must not start with 'b'; \
+             all[2]: Value 'b' of 'name' must have length between 2 and 3"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_is_skipped_when_its_resource_fails_to_load() {
+        let manifest: Manifest = serde_json::from_value(json!({
+            "name": "Resources",
+            "collect": "http://localhost",
+            "resources": [{ "uri": "file://./missing/rows.csv" }],
+            "pipeline": { "test": [{ "name": "Login", "request": "http://127.0.0.1:1/login" }] }
+        }))
+        .unwrap();
+        let report = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(App::new(manifest).run());
+
+        assert_eq!(report.steps.len(), 1);
+        assert_eq!(report.steps[0].name, "file://./missing/rows.csv");
+        assert_eq!(report.status(), Status::Error);
+    }
+
+    #[test]
+    fn test_secret_step_vars_are_registered_before_run() {
+        let manifest: Manifest = serde_json::from_value(json!({
+            "name": "Secrets",
+            "collect": "http://localhost",
+            "secrets": ["token"],
+            "tags": ["secrets"],
+            "pipeline": { "test": [{
+                "name": "Login",
+                "request": "http://127.0.0.1:1/login",
+                "vars": { "token": "43778f" }
+            }]}
+        }))
+        .unwrap();
+        let app = App::new(manifest).with_filter(Filter {
+            skip_tags: vec!["secrets".to_string()].into_iter().collect(),
+            ..Filter::default()
+        });
+        let report = tokio::runtime::Runtime::new().unwrap().block_on(app.run());
+
+        assert!(report.steps.is_empty());
+        assert_eq!(app.secrets.mask("token 43778f"), "token ***");
+    }
+
+    #[test]
+    fn test_secret_captured_from_response_is_masked_in_snapshot() {
+        let dir = std::env::temp_dir().join(format!("cannon-secret-{}", std::process::id()));
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        let report = runtime.block_on(async {
+            let service = hyper::service::make_service_fn(|_| async {
+                Ok::<_, hyper::Error>(hyper::service::service_fn(|_| async {
+                    Ok::<_, hyper::Error>(Response::new(Body::from(r#"{"token": "t0ken"}"#)))
+                }))
+            });
+            let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into()).serve(service);
+            let address = server.local_addr();
+            tokio::spawn(server);
+            let manifest: Manifest = serde_json::from_value(json!({
+                "name": "Secrets",
+                "collect": "http://localhost",
+                "pipeline": { "test": [{
+                    "name": "Login",
+                    "request": format!("http://{}/login", address),
+                    "capture": [{ "json": "$.token", "as": "token", "secret": true }],
+                    "snapshot": true
+                }]}
+            }))
+            .unwrap();
+            App::new(manifest)
+                .with_snapshots(Snapshots::new(&dir.join("secrets.yaml"), false))
+                .run()
+                .await
+        });
+        let stored = fs::read_to_string(dir.join("__snapshots__/secrets/Login.json"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.status(), Status::Passed);
+        let stored = stored.unwrap();
+        assert!(!stored.contains("t0ken"));
+        assert!(stored.contains("***"));
+    }
+
+    #[test]
+    fn test_context_prefers_step_vars_over_captures_over_manifest_vars() {
+        let manifest = Manifest {
+            vars: Object::from_iter(vec![
+                ("a".into(), LqValue::scalar("manifest")),
+                ("b".into(), LqValue::scalar("manifest")),
+                ("c".into(), LqValue::scalar("manifest")),
+            ]),
+            ..Manifest::default()
+        };
+        let app = App::new(manifest);
+        app.globals.lock().unwrap().extend(vec![
+            ("b".into(), LqValue::scalar("captured")),
+            ("c".into(), LqValue::scalar("captured")),
+        ]);
+        let vars = Object::from_iter(vec![("c".into(), LqValue::scalar("step"))]);
+        let context = app.context(&vars);
+
+        assert_eq!(context.get("a"), Some(&LqValue::scalar("manifest")));
+        assert_eq!(context.get("b"), Some(&LqValue::scalar("captured")));
+        assert_eq!(context.get("c"), Some(&LqValue::scalar("step")));
+    }
+
+    #[test]
+    fn test_captured_value_is_returned_when_assertions_pass() {
+        let app = App::new(Manifest::default());
+        let capture: Vec<CaptureEntry> = serde_json::from_value(json!([
+            { "json": "$.token", "as": "token", "on": [{ "assert": { "equal": { "value": "abc" } } }] },
+            { "json": "$.id", "as": "id", "on": [{ "assert": { "notequal": { "value": 1 } } }] }
+        ]))
+        .unwrap();
+        let mut result = StepResult::new("test");
+        let captured = app.capture(
+            StatusCode::OK,
+            &HeaderMap::new(),
+            br#"{"token": "abc", "id": 1}"#,
+            &capture,
+            &Object::new(),
+            &mut result,
+        );
+
+        assert_eq!(captured.get("token"), Some(&LqValue::scalar("abc")));
+        assert_eq!(captured.get("id"), None);
+        assert_eq!(result.status(), Status::Failed);
+    }
+
+    #[test]
+    fn test_capture_from_status_headers_and_cookies() {
+        let app = App::new(Manifest::default());
+        let capture: Vec<CaptureEntry> = serde_json::from_value(json!([
+            { "from": "status", "as": "status" },
+            { "from": "header:Location", "as": "location" },
+            { "from": "header:Location", "regex": "/items/(\\d+)", "as": "id" },
+            { "source": "header:X-Meta", "json": "$.page", "as": "page" },
+            { "from": "cookie:session", "as": "session" },
+            { "from": "cookie:missing", "as": "missing" },
+            { "from": "headers", "as": "headers" }
+        ]))
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("location", "/items/42".parse().unwrap());
+        headers.insert("x-meta", r#"{"page": 2}"#.parse().unwrap());
+        headers.append(SET_COOKIE, "theme=dark".parse().unwrap());
+        headers.append(
+            SET_COOKIE,
+            "session=s3cr3t; Path=/; HttpOnly".parse().unwrap(),
+        );
+        let mut result = StepResult::new("test");
+        let captured = app.capture(
+            StatusCode::CREATED,
+            &headers,
+            b"",
+            &capture,
+            &Object::new(),
+            &mut result,
+        );
+
+        assert_eq!(captured.get("status"), Some(&LqValue::scalar(201)));
+        assert_eq!(
+            captured.get("location"),
+            Some(&LqValue::scalar("/items/42"))
+        );
+        assert_eq!(captured.get("id"), Some(&LqValue::scalar("42")));
+        assert_eq!(captured.get("page"), Some(&LqValue::scalar(2)));
+        assert_eq!(captured.get("session"), Some(&LqValue::scalar("s3cr3t")));
+        assert_eq!(captured.get("missing"), Some(&LqValue::Nil));
+        match captured.get("headers") {
+            Some(LqValue::Object(object)) => {
+                assert_eq!(
+                    object.get("set-cookie"),
+                    Some(&LqValue::scalar(
+                        "theme=dark, session=s3cr3t; Path=/; HttpOnly"
+                    ))
+                )
+            }
+            other => panic!("Expected headers object but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_capture_source_and_extractor_are_rejected() {
+        let source =
+            serde_json::from_value::<CaptureEntry>(json!({ "from": "cookies", "as": "a" }));
+        let regex = serde_json::from_value::<CaptureEntry>(json!({ "regex": "(", "as": "a" }));
+        let json = serde_json::from_value::<CaptureEntry>(json!({ "json": "$.a[?", "as": "a" }));
+        let jmespath =
+            serde_json::from_value::<CaptureEntry>(json!({ "jmespath": "a[", "as": "a" }));
+
+        assert!(source.is_err());
+        assert!(regex.is_err());
+        assert!(json.is_err());
+        assert!(jmespath.is_err());
+    }
+
+    #[test]
+    fn test_invalid_templates_are_reported_with_path() {
+        let entry = serde_json::from_value::<PipelineEntry>(json!({
+            "name": "Templates",
+            "request": "http://localhost/{{ id | unknown_filter }}",
+            "headers": { "X-Id": "{% uuid %}", "X-Bad": "{{ unclosed" },
+            "body": { "raw": "{% random_int 1 %}" }
+        }))
+        .unwrap();
+        let mut manifest = Manifest::default();
+        manifest.pipeline.test.push(entry);
+        let app = App::new(manifest);
+
+        let mut paths: Vec<String> = app.validate().into_iter().map(|e| e.path).collect();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![
+                "pipeline.test[0].body",
+                "pipeline.test[0].headers.X-Bad",
+                "pipeline.test[0].request",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_schemas_are_rejected() {
+        let entry = |schema: Value| {
+            serde_json::from_value::<PipelineEntry>(json!({
+                "name": "Schema",
+                "request": "http://localhost",
+                "schema": schema
+            }))
+        };
+        let mut manifest = Manifest::default();
+        manifest
+            .pipeline
+            .test
+            .push(entry(json!({ "type": 1 })).unwrap());
+        manifest
+            .pipeline
+            .test
+            .push(entry(json!("file://./missing.json")).unwrap());
+        manifest.pipeline.test.push(entry(json!(true)).unwrap());
+        let app = App::new(manifest);
+
+        let paths: Vec<String> = app.validate().into_iter().map(|e| e.path).collect();
+
+        assert_eq!(
+            paths,
+            vec!["pipeline.test[0].schema", "pipeline.test[1].schema"]
+        );
+        assert!(entry(json!("file:///abs/user.json")).is_err());
+        assert!(entry(json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_json_path_filters_and_jmespath_capture() {
+        let app = App::new(Manifest::default());
+        let capture: Vec<CaptureEntry> = serde_json::from_value(json!([
+            { "json": "$.items[?(@.status=='active')].id", "as": "active" },
+            { "json": "$..id", "as": "ids" },
+            { "json": "$.items[1:]", "as": "tail" },
+            { "json": "$.items[?length(@.tags) > 1].id", "as": "tagged" },
+            { "jmespath": "items[?status=='blocked'].id | [0]", "as": "blocked" },
+            { "jmespath": "length(items)", "as": "count" }
+        ]))
+        .unwrap();
+        let body = br#"{"items": [
+            {"id": 1, "status": "active", "tags": ["a", "b"]},
+            {"id": 2, "status": "blocked", "tags": []},
+            {"id": 3, "status": "active", "tags": ["c"]}
+        ]}"#;
+        let mut result = StepResult::new("test");
+        let captured = app.capture(
+            StatusCode::OK,
+            &HeaderMap::new(),
+            body,
+            &capture,
+            &Object::new(),
+            &mut result,
+        );
+        let ids = |ids: Vec<i64>| LqValue::Array(ids.into_iter().map(LqValue::scalar).collect());
+
+        assert_eq!(captured.get("active"), Some(&ids(vec![1, 3])));
+        assert_eq!(captured.get("ids"), Some(&ids(vec![1, 2, 3])));
+        assert!(matches!(captured.get("tail"), Some(LqValue::Array(tail)) if tail.len() == 2));
+        assert_eq!(captured.get("tagged"), Some(&LqValue::scalar(1)));
+        assert_eq!(captured.get("blocked"), Some(&LqValue::scalar(2)));
+        assert_eq!(captured.get("count"), Some(&LqValue::scalar(3)));
+        assert_eq!(result.status(), Status::Passed);
+    }
+
+    #[test]
+    fn test_value_matches_pattern() {
+        let matcher = matcher::deserialize(json!("^\\d+$")).unwrap();
+
+        assert!(match_value(&LqValue::scalar("42"), &matcher).is_ok());
+        assert!(match_value(&LqValue::scalar("abc"), &matcher).is_err());
+        assert!(match_value(&LqValue::scalar(42), &matcher).is_err());
+        assert!(match_value(&LqValue::Nil, &matcher).is_err());
+    }
+
+    #[test]
+    fn test_array_matches_pattern_in_every_and_any_mode() {
+        let every = matcher::deserialize(json!({ "pattern": "^\\d+$" })).unwrap();
+        let any = matcher::deserialize(json!({ "pattern": "^\\d+$", "mode": "any" })).unwrap();
+        let digits = LqValue::Array(vec![LqValue::scalar("1"), LqValue::scalar("2")]);
+        let mixed = LqValue::Array(vec![LqValue::scalar("1"), LqValue::scalar("a")]);
+        let letters = LqValue::Array(vec![LqValue::scalar("a"), LqValue::scalar("b")]);
+
+        assert!(match_value(&digits, &every).is_ok());
+        assert!(match_value(&mixed, &every).is_err());
+        assert!(match_value(&mixed, &any).is_ok());
+        assert!(match_value(&letters, &any).is_err());
+    }
+
+    #[test]
+    fn test_regex_capture_of_named_groups() {
+        let regex = Regex::new(r"(?P<key>\w+)=(?P<value>\w+)").unwrap();
+        let value = capture_regex(&regex, "token=abc");
+        let object: Object = vec![
+            ("key".into(), LqValue::scalar("token")),
+            ("value".into(), LqValue::scalar("abc")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(value, LqValue::Object(object));
+    }
+
+    #[test]
+    fn test_regex_capture_of_numbered_groups() {
+        let single = Regex::new(r"token=(\w+)").unwrap();
+        let numbered = Regex::new(r"(\w+)=(\w+)").unwrap();
+
+        assert_eq!(capture_regex(&single, "token=abc"), LqValue::scalar("abc"));
+        assert_eq!(
+            capture_regex(&numbered, "token=abc"),
+            LqValue::Array(vec![LqValue::scalar("token"), LqValue::scalar("abc")])
+        );
+    }
+
+    #[test]
+    fn test_regex_capture_of_multiple_matches() {
+        let regex = Regex::new(r"\d+").unwrap();
+
+        assert_eq!(
+            capture_regex(&regex, "1, 22, 333"),
+            LqValue::Array(vec![
+                LqValue::scalar("1"),
+                LqValue::scalar("22"),
+                LqValue::scalar("333")
+            ])
+        );
+        assert_eq!(capture_regex(&regex, "none"), LqValue::Nil);
+    }
+
+    #[test]
+    fn test_xpath_capture_converts_values() {
+        let xml = r#"<?xml version="1.0"?>
+            <soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"
+                           xmlns:m="http://example.com/users">
+                <soap:Body>
+                    <m:User id="7"><m:Name>Alice</m:Name></m:User>
+                    <m:User id="8"><m:Name>Bob</m:Name></m:User>
+                </soap:Body>
+            </soap:Envelope>"#;
+        let namespaces: HashMap<String, String> = vec![
+            (
+                "s".to_string(),
+                "http://schemas.xmlsoap.org/soap/envelope/".to_string(),
+            ),
+            ("u".to_string(), "http://example.com/users".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let capture = |expression: &str| {
+            let xpath = sxd_xpath::Factory::new()
+                .build(expression)
+                .unwrap()
+                .unwrap();
+            capture_xpath(&xpath, &namespaces, xml).unwrap()
+        };
+
+        assert_eq!(capture("//u:User[1]/u:Name"), LqValue::scalar("Alice"));
+        assert_eq!(
+            capture("/s:Envelope/s:Body/u:User/@id"),
+            LqValue::Array(vec![LqValue::scalar("7"), LqValue::scalar("8")])
+        );
+        assert_eq!(capture("count(//u:User)"), LqValue::scalar(2));
+        assert_eq!(capture("count(//u:User) div 4"), LqValue::scalar(0.5));
+        assert_eq!(
+            capture("string(//u:User[2]/u:Name)"),
+            LqValue::scalar("Bob")
+        );
+        assert_eq!(capture("boolean(//u:Missing)"), LqValue::scalar(false));
+        assert_eq!(capture("//u:Missing"), LqValue::Nil);
+    }
+
     #[test]
     fn test_convertation_into_liquid_value() {
         let value_null = json!(null);
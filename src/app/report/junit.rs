@@ -0,0 +1,177 @@
+use super::{RunReport, Status, StepResult, SuiteReport};
+use crate::configuration::constants::cargo_env::CARGO_PKG_NAME;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub fn write(report: &RunReport, path: &Path) -> io::Result<()> {
+    fs::write(path, render(report))
+}
+
+pub fn write_suite(suite: &SuiteReport, path: &Path) -> io::Result<()> {
+    fs::write(path, render_suite(suite))
+}
+
+/// Renders report as JUnit XML, manifest becomes a testsuite
+/// and every pipeline step becomes a testcase.
+pub fn render(report: &RunReport) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        escape(&report.name),
+        report.steps.len(),
+        report.count(Status::Failed),
+        report.count(Status::Error),
+        report.elapsed.as_secs_f64()
+    ));
+    render_testsuite(&mut xml, report);
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Renders reports of all manifests of suite as JUnit XML, every manifest becomes a testsuite
+pub fn render_suite(suite: &SuiteReport) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        CARGO_PKG_NAME,
+        suite
+            .reports
+            .iter()
+            .map(|report| report.steps.len())
+            .sum::<usize>(),
+        suite.count(Status::Failed),
+        suite.count(Status::Error),
+        suite.elapsed.as_secs_f64()
+    ));
+    for report in &suite.reports {
+        render_testsuite(&mut xml, report);
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn render_testsuite(xml: &mut String, report: &RunReport) {
+    let name = escape(&report.name);
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        name,
+        report.steps.len(),
+        report.count(Status::Failed),
+        report.count(Status::Error),
+        report.elapsed.as_secs_f64()
+    ));
+    for step in &report.steps {
+        render_testcase(xml, &name, step);
+    }
+    xml.push_str("  </testsuite>\n");
+}
+
+fn render_testcase(xml: &mut String, classname: &str, step: &StepResult) {
+    xml.push_str(&format!(
+        "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+        escape(&step.name),
+        classname,
+        step.elapsed.as_secs_f64()
+    ));
+    for failure in step.failures() {
+        let message = escape(&failure.message);
+        xml.push_str(&format!(
+            "      <failure message=\"{}\" type=\"assertion\">{}</failure>\n",
+            message, message
+        ));
+    }
+    for error in &step.errors {
+        let message = escape(error);
+        xml.push_str(&format!(
+            "      <error message=\"{}\">{}</error>\n",
+            message, message
+        ));
+    }
+    let summary: Vec<&str> = step
+        .request
+        .iter()
+        .chain(step.response.iter())
+        .map(String::as_str)
+        .collect();
+    if !summary.is_empty() {
+        xml.push_str(&format!(
+            "      <system-out>{}</system-out>\n",
+            escape(&summary.join("\n"))
+        ));
+    }
+    xml.push_str("    </testcase>\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_report_rendered_as_junit_xml() {
+        let mut report = RunReport::new("Suite <1>");
+        report.elapsed = Duration::from_millis(1500);
+        let mut passed = StepResult::new("passed");
+        passed.elapsed = Duration::from_millis(250);
+        passed.request = Some("GET http://localhost/".into());
+        passed.response = Some("HTTP/1.1 200 OK".into());
+        let mut failed = StepResult::new("failed");
+        failed.assert(false, "Value must be \"a\"".into());
+        let mut errored = StepResult::new("errored");
+        errored.error("Failed to send request".into());
+        report.steps = vec![passed, failed, errored];
+
+        let xml = render(&report);
+
+        assert!(xml.contains(
+            "<testsuite name=\"Suite &lt;1&gt;\" tests=\"3\" failures=\"1\" errors=\"1\" time=\"1.500\">"
+        ));
+        assert!(
+            xml.contains("<testcase name=\"passed\" classname=\"Suite &lt;1&gt;\" time=\"0.250\">")
+        );
+        assert!(xml.contains("<system-out>GET http://localhost/\nHTTP/1.1 200 OK</system-out>"));
+        assert!(
+            xml.contains("<failure message=\"Value must be &quot;a&quot;\" type=\"assertion\">")
+        );
+        assert!(xml.contains("<error message=\"Failed to send request\">"));
+    }
+
+    #[test]
+    fn test_suite_rendered_with_testsuite_per_manifest() {
+        let mut first = RunReport::new("First");
+        first.steps.push(StepResult::new("passed"));
+        let mut second = RunReport::new("Second");
+        second.steps.push(StepResult::new("errored"));
+        second.steps[0].error("Failed to load manifest".into());
+        let suite = SuiteReport {
+            reports: vec![first, second],
+            elapsed: Duration::from_millis(2000),
+        };
+
+        let xml = render_suite(&suite);
+
+        assert!(xml.contains(
+            "<testsuites name=\"cannon\" tests=\"2\" failures=\"0\" errors=\"1\" time=\"2.000\">"
+        ));
+        assert!(xml.contains("<testsuite name=\"First\" tests=\"1\" failures=\"0\" errors=\"0\""));
+        assert!(xml.contains("<testsuite name=\"Second\" tests=\"1\" failures=\"0\" errors=\"1\""));
+    }
+}
@@ -0,0 +1,257 @@
+use crate::app::Secrets;
+use crate::configuration::constants::exit_code;
+use liquid::Object;
+use std::time::Duration;
+
+pub mod junit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Passed,
+    Failed,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssertionResult {
+    pub passed: bool,
+    pub message: String,
+}
+
+/// Outcome of a single pipeline step
+#[derive(Debug, Default)]
+pub struct StepResult {
+    pub name: String,
+    pub assertions: Vec<AssertionResult>,
+    pub errors: Vec<String>,
+    pub elapsed: Duration,
+    pub captured: Object,
+    pub request: Option<String>,
+    pub response: Option<String>,
+}
+
+/// Outcome of the whole pipeline run
+#[derive(Debug, Default)]
+pub struct RunReport {
+    pub name: String,
+    pub steps: Vec<StepResult>,
+    pub elapsed: Duration,
+    /// Manifest failed to load, steps hold only errors of loading
+    pub invalid: bool,
+}
+
+/// Outcome of run of many manifests, every manifest has its own report
+#[derive(Debug, Default)]
+pub struct SuiteReport {
+    pub reports: Vec<RunReport>,
+    pub elapsed: Duration,
+}
+
+impl Status {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Status::Passed => exit_code::SUCCESS,
+            Status::Failed => exit_code::FAILED,
+            Status::Error => exit_code::ERROR,
+        }
+    }
+}
+
+impl StepResult {
+    pub fn new(name: &str) -> Self {
+        StepResult {
+            name: name.to_string(),
+            ..StepResult::default()
+        }
+    }
+
+    pub fn assert(&mut self, passed: bool, message: String) {
+        trace!("Assert result {}", passed);
+        if !passed {
+            info!("Assertation failed: {}", message);
+        }
+        self.assertions.push(AssertionResult { passed, message });
+    }
+
+    pub fn error(&mut self, message: String) {
+        error!("{}", message);
+        self.errors.push(message);
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &AssertionResult> {
+        self.assertions.iter().filter(|assertion| !assertion.passed)
+    }
+
+    /// Masks secret values in name, messages, summaries and captured values
+    pub fn redact(&mut self, secrets: &Secrets) {
+        self.name = secrets.mask(&self.name);
+        for assertion in &mut self.assertions {
+            assertion.message = secrets.mask(&assertion.message);
+        }
+        for error in &mut self.errors {
+            *error = secrets.mask(error);
+        }
+        for summary in self.request.iter_mut().chain(self.response.iter_mut()) {
+            *summary = secrets.mask(summary);
+        }
+        for value in self.captured.values_mut() {
+            *value = secrets.mask_liquid(value);
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        if !self.errors.is_empty() {
+            Status::Error
+        } else if self.failures().next().is_some() {
+            Status::Failed
+        } else {
+            Status::Passed
+        }
+    }
+}
+
+impl RunReport {
+    pub fn new(name: &str) -> Self {
+        RunReport {
+            name: name.to_string(),
+            ..RunReport::default()
+        }
+    }
+
+    pub fn redact(&mut self, secrets: &Secrets) {
+        for step in &mut self.steps {
+            step.redact(secrets);
+        }
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| step.status() == status)
+            .count()
+    }
+
+    pub fn status(&self) -> Status {
+        self.steps
+            .iter()
+            .map(StepResult::status)
+            .max()
+            .unwrap_or(Status::Passed)
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        if self.invalid {
+            exit_code::INVALID_MANIFEST
+        } else {
+            self.status().exit_code()
+        }
+    }
+}
+
+impl SuiteReport {
+    pub fn count(&self, status: Status) -> usize {
+        self.reports.iter().map(|report| report.count(status)).sum()
+    }
+
+    /// Exit codes grow with severity, so the code of the worst manifest is the greatest one
+    pub fn exit_code(&self) -> i32 {
+        self.reports
+            .iter()
+            .map(RunReport::exit_code)
+            .max()
+            .unwrap_or(exit_code::SUCCESS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_step_status_depends_on_assertions_and_errors() {
+        let mut step = StepResult::new("step");
+        assert_eq!(step.status(), Status::Passed);
+
+        step.assert(true, "passed".into());
+        assert_eq!(step.status(), Status::Passed);
+
+        step.assert(false, "failed".into());
+        assert_eq!(step.status(), Status::Failed);
+
+        step.error("error".into());
+        assert_eq!(step.status(), Status::Error);
+    }
+
+    #[test]
+    fn test_report_exit_code_reflects_worst_step() {
+        let mut report = RunReport::new("report");
+        report.steps.push(StepResult::new("passed"));
+        assert_eq!(report.exit_code(), exit_code::SUCCESS);
+
+        let mut failed = StepResult::new("failed");
+        failed.assert(false, "failed".into());
+        report.steps.push(failed);
+        assert_eq!(report.exit_code(), exit_code::FAILED);
+        assert_eq!(report.count(Status::Failed), 1);
+
+        let mut errored = StepResult::new("errored");
+        errored.error("error".into());
+        report.steps.push(errored);
+        assert_eq!(report.exit_code(), exit_code::ERROR);
+        assert_eq!(report.count(Status::Passed), 1);
+    }
+
+    #[test]
+    fn test_suite_exit_code_reflects_worst_manifest() {
+        let mut passed = RunReport::new("passed");
+        passed.steps.push(StepResult::new("passed"));
+        let mut failed = RunReport::new("failed");
+        failed.steps.push(StepResult::new("passed"));
+        failed.steps.push(StepResult::new("failed"));
+        failed.steps[1].assert(false, "failed".into());
+        let mut suite = SuiteReport::default();
+        assert_eq!(suite.exit_code(), exit_code::SUCCESS);
+
+        suite.reports = vec![passed, failed];
+
+        assert_eq!(suite.exit_code(), exit_code::FAILED);
+        assert_eq!(suite.count(Status::Passed), 2);
+        assert_eq!(suite.count(Status::Failed), 1);
+
+        let mut invalid = RunReport::new("invalid");
+        invalid.steps.push(StepResult::new("load"));
+        invalid.steps[0].error("Failed to load manifest".into());
+        invalid.invalid = true;
+        suite.reports.push(invalid);
+        assert_eq!(suite.exit_code(), exit_code::INVALID_MANIFEST);
+    }
+
+    #[test]
+    fn test_report_redacts_secrets() {
+        let secrets = Secrets::default();
+        secrets.add("t0ken");
+        let mut step = StepResult::new("step");
+        step.assert(false, "Value 't0ken' must be 'other'".into());
+        step.error("Failed with t0ken".into());
+        step.request = Some("GET http://localhost/?token=t0ken".into());
+        step.captured
+            .insert("token".into(), liquid::model::Value::scalar("t0ken"));
+        let mut report = RunReport::new("report");
+        report.steps.push(step);
+
+        report.redact(&secrets);
+
+        let step = &report.steps[0];
+        assert_eq!(step.assertions[0].message, "Value '***' must be 'other'");
+        assert_eq!(step.errors[0], "Failed with ***");
+        assert_eq!(
+            step.request.as_deref(),
+            Some("GET http://localhost/?token=***")
+        );
+        assert_eq!(
+            step.captured.get("token"),
+            Some(&liquid::model::Value::scalar("***"))
+        );
+    }
+}
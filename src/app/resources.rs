@@ -0,0 +1,114 @@
+use crate::app::{read_uri, IntoLiquid};
+use crate::configuration::manifest::{DataFormat, Resource};
+use liquid::model::Value as LqValue;
+use liquid::Object;
+use serde_json::Value;
+
+/// Loads data rows of the resource, every row becomes an object
+/// with column (or key) names as variable names.
+pub fn load(resource: &Resource) -> Result<Vec<Object>, String> {
+    let data =
+        read_uri(&resource.uri).ok_or_else(|| format!("Cannot load resource {}", resource.uri))?;
+    let format = match &resource.format {
+        Some(format) => format,
+        None => detect_format(resource)?,
+    };
+    match format {
+        DataFormat::Csv => parse_csv(&data),
+        DataFormat::Json => parse_json(&data),
+        DataFormat::Jsonl => parse_json_lines(&data),
+    }
+}
+
+fn detect_format(resource: &Resource) -> Result<&'static DataFormat, String> {
+    let path = resource.uri.path().to_lowercase();
+    if path.ends_with(".csv") {
+        Ok(&DataFormat::Csv)
+    } else if path.ends_with(".json") {
+        Ok(&DataFormat::Json)
+    } else if path.ends_with(".jsonl") || path.ends_with(".ndjson") {
+        Ok(&DataFormat::Jsonl)
+    } else {
+        Err(format!(
+            "Cannot detect format of resource {}, define it with 'format' property",
+            resource.uri
+        ))
+    }
+}
+
+fn parse_csv(data: &[u8]) -> Result<Vec<Object>, String> {
+    let mut reader = csv::Reader::from_reader(data);
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| e.to_string())?;
+            Ok(headers
+                .iter()
+                .zip(record.iter())
+                .map(|(key, value)| (key.to_string().into(), LqValue::scalar(value.to_string())))
+                .collect())
+        })
+        .collect()
+}
+
+fn parse_json(data: &[u8]) -> Result<Vec<Object>, String> {
+    match serde_json::from_slice(data).map_err(|e| e.to_string())? {
+        Value::Array(rows) => rows.iter().map(into_row).collect(),
+        _ => Err(String::from(
+            "Json resource must contain an array of objects",
+        )),
+    }
+}
+
+fn parse_json_lines(data: &[u8]) -> Result<Vec<Object>, String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| e.to_string())
+                .and_then(|row| into_row(&row))
+        })
+        .collect()
+}
+
+fn into_row(value: &Value) -> Result<Object, String> {
+    match value.into_liquid() {
+        LqValue::Object(row) => Ok(row),
+        _ => Err(format!("Data row must be an object but got {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_csv_rows_use_header_as_variable_names() {
+        let rows = parse_csv(b"username,password\nadmin,secret\nguest,guest\n").unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get("username"), Some(&LqValue::scalar("admin")));
+        assert_eq!(rows[1].get("password"), Some(&LqValue::scalar("guest")));
+    }
+
+    #[test]
+    fn test_json_array_rows() {
+        let rows = parse_json(br#"[{"id": 1}, {"id": 2}]"#).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].get("id"), Some(&LqValue::scalar(2)));
+        assert!(parse_json(br#"{"id": 1}"#).is_err());
+        assert!(parse_json(br#"[1, 2]"#).is_err());
+    }
+
+    #[test]
+    fn test_json_lines_rows() {
+        let rows = parse_json_lines(b"{\"id\": 1}\n\n{\"id\": 2}\n").unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get("id"), Some(&LqValue::scalar(1)));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::app::read_uri;
+use crate::configuration::manifest::SchemaEntry;
+use jsonschema::Validator;
+use serde_json::Value;
+
+/// Loads and compiles JSON schema, it is done once when pipeline is created
+pub fn compile(schema: &SchemaEntry) -> Result<Validator, String> {
+    let schema = load(schema)?;
+    jsonschema::validator_for(&schema).map_err(|e| format!("Invalid JSON schema: {}", e))
+}
+
+/// Validates response body against JSON schema.
+/// Returns every violation with its instance path and keyword or single passed
+/// check if body matches the schema.
+pub fn check(validator: &Validator, body: &[u8]) -> Vec<(bool, String)> {
+    let instance: Value = match serde_json::from_slice(body) {
+        Ok(instance) => instance,
+        Err(e) => return vec![(false, format!("Response body is not valid JSON: {}", e))],
+    };
+    let violations: Vec<(bool, String)> = validator
+        .iter_errors(&instance)
+        .map(|error| {
+            let path = error.instance_path().to_string();
+            (
+                false,
+                format!(
+                    "Schema violation at '{}' ({}): {}",
+                    if path.is_empty() { "/" } else { &path },
+                    error.kind().keyword(),
+                    error
+                ),
+            )
+        })
+        .collect();
+    if violations.is_empty() {
+        vec![(true, "Response body matches schema".into())]
+    } else {
+        violations
+    }
+}
+
+fn load(schema: &SchemaEntry) -> Result<Value, String> {
+    match schema {
+        SchemaEntry::Inline(schema) => Ok(schema.clone()),
+        SchemaEntry::Uri(uri) => read_uri(uri)
+            .ok_or_else(|| format!("Cannot load JSON schema from {}", uri))
+            .and_then(|data| {
+                serde_json::from_slice(&data)
+                    .map_err(|e| format!("Invalid JSON schema {}: {}", uri, e))
+            }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Validator {
+        compile(&SchemaEntry::Inline(json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": { "type": "integer" },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            }
+        })))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_body_matches_schema() {
+        let results = check(&schema(), br#"{"id": 1, "name": "test", "tags": ["a"]}"#);
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].0);
+    }
+
+    #[test]
+    fn test_every_violation_is_reported() {
+        let results = check(&schema(), br#"{"id": "1", "tags": ["a", 2]}"#);
+        let messages: Vec<&String> = results.iter().map(|(_, message)| message).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(passed, _)| !passed));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("Schema violation at '/' (required)")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("Schema violation at '/id' (type)")));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("Schema violation at '/tags/1' (type)")));
+    }
+
+    #[test]
+    fn test_invalid_body_and_schema() {
+        let uri = "file://./missing/user.schema.json".parse().unwrap();
+
+        assert!(!check(&schema(), b"not json")[0].0);
+        assert!(compile(&SchemaEntry::Inline(json!({ "type": 1 }))).is_err());
+        assert_eq!(
+            compile(&SchemaEntry::Uri(uri)).err(),
+            Some("Cannot load JSON schema from file://./missing/user.schema.json".to_string())
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use liquid::model::Value as LqValue;
+use liquid::ValueView;
+use percent_encoding::NON_ALPHANUMERIC;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+const MASK: &str = "***";
+
+/// Values of secret variables shared by logger, reports and snapshots,
+/// every known value is replaced with mask in produced output.
+#[derive(Clone, Debug, Default)]
+pub struct Secrets(Arc<RwLock<Vec<String>>>);
+
+impl Secrets {
+    /// Registers secret value together with its percent-encoded and JSON-escaped forms,
+    /// so the value is masked inside URLs and bodies too.
+    pub fn add(&self, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+        let encoded = percent_encoding::utf8_percent_encode(value, NON_ALPHANUMERIC).to_string();
+        let quoted = serde_json::to_string(value).unwrap_or_default();
+        let escaped = quoted[1..quoted.len() - 1].to_owned();
+        let mut values = self.0.write().unwrap();
+        for form in [value.to_owned(), encoded, escaped] {
+            if !values.contains(&form) {
+                values.push(form);
+            }
+        }
+        // Longer values go first, so secret containing another one is masked entirely
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    }
+
+    /// Registers every scalar of the value
+    pub fn add_value(&self, value: &LqValue) {
+        match value {
+            LqValue::Nil => {}
+            LqValue::Array(values) => values.iter().for_each(|value| self.add_value(value)),
+            LqValue::Object(values) => values.values().for_each(|value| self.add_value(value)),
+            value => self.add(&value.to_kstr()),
+        }
+    }
+
+    pub fn mask(&self, text: &str) -> String {
+        let values = self.0.read().unwrap();
+        let mut masked = text.to_owned();
+        for value in values.iter() {
+            if masked.contains(value.as_str()) {
+                masked = masked.replace(value.as_str(), MASK);
+            }
+        }
+        masked
+    }
+
+    pub fn mask_json(&self, value: &Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.mask(text)),
+            Value::Array(values) => {
+                Value::Array(values.iter().map(|v| self.mask_json(v)).collect())
+            }
+            Value::Object(values) => Value::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.mask_json(value)))
+                    .collect(),
+            ),
+            value => value.clone(),
+        }
+    }
+
+    pub fn mask_liquid(&self, value: &LqValue) -> LqValue {
+        match value {
+            LqValue::Scalar(scalar) => {
+                let text = scalar.to_kstr();
+                let masked = self.mask(&text);
+                if masked == text.as_str() {
+                    value.clone()
+                } else {
+                    LqValue::scalar(masked)
+                }
+            }
+            LqValue::Array(values) => {
+                LqValue::Array(values.iter().map(|v| self.mask_liquid(v)).collect())
+            }
+            LqValue::Object(values) => LqValue::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.mask_liquid(value)))
+                    .collect(),
+            ),
+            value => value.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_masked_in_raw_encoded_and_escaped_forms() {
+        let secrets = Secrets::default();
+        secrets.add("p@ss \"word\"");
+        secrets.add("");
+
+        assert_eq!(
+            secrets.mask("GET http://host/login?password=p%40ss%20%22word%22"),
+            "GET http://host/login?password=***"
+        );
+        assert_eq!(
+            secrets.mask("{\"password\":\"p@ss \\\"word\\\"\"}"),
+            "{\"password\":\"***\"}"
+        );
+        assert_eq!(
+            secrets.mask("Authorization: p@ss \"word\""),
+            "Authorization: ***"
+        );
+        assert_eq!(secrets.mask("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn test_longer_secret_masked_entirely() {
+        let secrets = Secrets::default();
+        secrets.add("abc");
+        secrets.add("abcdef");
+
+        assert_eq!(secrets.mask("token abcdef"), "token ***");
+    }
+
+    #[test]
+    fn test_nested_values_masked() {
+        let secrets = Secrets::default();
+        secrets.add_value(&LqValue::Array(vec![
+            LqValue::scalar("t0ken"),
+            LqValue::Nil,
+        ]));
+
+        assert_eq!(
+            secrets.mask_json(&json!({"auth": {"token": "t0ken"}, "id": 1})),
+            json!({"auth": {"token": "***"}, "id": 1})
+        );
+        assert_eq!(
+            secrets.mask_liquid(&LqValue::Array(vec![
+                LqValue::scalar("Bearer t0ken"),
+                LqValue::scalar(1)
+            ])),
+            LqValue::Array(vec![LqValue::scalar("Bearer ***"), LqValue::scalar(1)])
+        );
+    }
+}
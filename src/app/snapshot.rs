@@ -0,0 +1,278 @@
+use crate::app::Secrets;
+use crate::configuration::manifest::{Snapshot, SnapshotEntry};
+use hyper::{HeaderMap, StatusCode};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const IGNORED: &str = "<ignored>";
+
+/// Stores normalized responses as golden files and compares later runs against them.
+#[derive(Debug)]
+pub struct Snapshots {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl Default for Snapshots {
+    fn default() -> Self {
+        Snapshots {
+            dir: PathBuf::from("__snapshots__"),
+            update: false,
+        }
+    }
+}
+
+impl Snapshots {
+    /// Snapshots of manifest are placed into `__snapshots__/<manifest file stem>` next to the manifest file
+    pub fn new(manifest: &Path, update: bool) -> Self {
+        let stem = manifest
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dir = manifest
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join("__snapshots__")
+            .join(stem);
+        Snapshots { dir, update }
+    }
+
+    /// Stores snapshot of response if it does not exist or update mode is enabled,
+    /// otherwise returns every difference between stored and received response.
+    /// Secret values are masked before response is stored or compared.
+    pub fn check(
+        &self,
+        name: &str,
+        entry: &SnapshotEntry,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+        secrets: &Secrets,
+    ) -> Result<Vec<(bool, String)>, String> {
+        let default = Snapshot::default();
+        let snapshot = match entry {
+            SnapshotEntry::Enabled(false) => return Ok(vec![]),
+            SnapshotEntry::Enabled(true) => &default,
+            SnapshotEntry::Options(snapshot) => snapshot,
+        };
+        let actual = secrets.mask_json(&normalize(snapshot, status, headers, body));
+        let path = self.path(name);
+        if self.update || !path.exists() {
+            let content = serde_json::to_string_pretty(&actual).map_err(|e| e.to_string())?;
+            fs::create_dir_all(&self.dir)
+                .and_then(|_| fs::write(&path, content + "\n"))
+                .map_err(|e| format!("Failed to store snapshot {}: {}", path.display(), e))?;
+            return Ok(vec![(
+                true,
+                format!("Snapshot stored to {}", path.display()),
+            )]);
+        }
+        let expected: Value = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_slice(&data).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to read snapshot {}: {}", path.display(), e))?;
+        let mut differences = Vec::new();
+        diff("", &expected, &actual, &mut differences);
+        if differences.is_empty() {
+            Ok(vec![(true, "Response matches snapshot".into())])
+        } else {
+            Ok(differences
+                .into_iter()
+                .map(|difference| (false, format!("Snapshot mismatch {}", difference)))
+                .collect())
+        }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        let file: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file))
+    }
+}
+
+/// Builds snapshot document of response, body is parsed as JSON if possible
+/// and values matched by ignore paths are replaced with placeholder.
+fn normalize(snapshot: &Snapshot, status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Value {
+    let mut document = Map::new();
+    if snapshot.status {
+        document.insert("status".into(), Value::from(status.as_u16()));
+    }
+    if !snapshot.headers.is_empty() {
+        let selected = snapshot
+            .headers
+            .iter()
+            .map(|name| {
+                let values: Vec<&str> = headers
+                    .get_all(name.as_str())
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .collect();
+                let value = if values.is_empty() {
+                    Value::Null
+                } else {
+                    Value::from(values.join(", "))
+                };
+                (name.to_lowercase(), value)
+            })
+            .collect();
+        document.insert("headers".into(), Value::Object(selected));
+    }
+    let body = match serde_json::from_slice::<Value>(body) {
+        Ok(json) => {
+            let ignored: HashSet<*const Value> = snapshot
+                .ignore
+                .iter()
+                .flat_map(|selector| selector.query(&json).all())
+                .map(|value| value as *const Value)
+                .collect();
+            mask(&json, &ignored)
+        }
+        Err(_) => Value::from(String::from_utf8_lossy(body).into_owned()),
+    };
+    document.insert("body".into(), body);
+    Value::Object(document)
+}
+
+fn mask(value: &Value, ignored: &HashSet<*const Value>) -> Value {
+    if ignored.contains(&(value as *const Value)) {
+        return Value::from(IGNORED);
+    }
+    match value {
+        Value::Array(array) => Value::Array(array.iter().map(|v| mask(v, ignored)).collect()),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, v)| (key.clone(), mask(v, ignored)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+/// Collects differences in form `~ /path: expected -> actual`,
+/// `- /path: expected` for missing and `+ /path: actual` for new values.
+fn diff(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                let path = format!("{}/{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => diff(&path, expected, actual, differences),
+                    None => differences.push(format!("- {}: {}", path, expected)),
+                }
+            }
+            for (key, actual) in actual {
+                if !expected.contains_key(key) {
+                    differences.push(format!("+ {}/{}: {}", path, key, actual));
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for index in 0..expected.len().max(actual.len()) {
+                let path = format!("{}/{}", path, index);
+                match (expected.get(index), actual.get(index)) {
+                    (Some(expected), Some(actual)) => diff(&path, expected, actual, differences),
+                    (Some(expected), None) => differences.push(format!("- {}: {}", path, expected)),
+                    (None, Some(actual)) => differences.push(format!("+ {}: {}", path, actual)),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if expected != actual => differences.push(format!(
+            "~ {}: {} -> {}",
+            if path.is_empty() { "/" } else { path },
+            expected,
+            actual
+        )),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalized_body_masks_ignored_paths() {
+        let snapshot: Snapshot = serde_json::from_value(json!({
+            "status": true,
+            "headers": ["Content-Type"],
+            "ignore": ["$.id", "$.items[*].created"]
+        }))
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        let body = br#"{"id": 7, "items": [{"name": "a", "created": 1}]}"#;
+
+        assert_eq!(
+            normalize(&snapshot, StatusCode::OK, &headers, body),
+            json!({
+                "status": 200,
+                "headers": { "content-type": "application/json" },
+                "body": { "id": IGNORED, "items": [{ "name": "a", "created": IGNORED }] }
+            })
+        );
+    }
+
+    #[test]
+    fn test_structural_diff() {
+        let mut differences = Vec::new();
+        diff(
+            "",
+            &json!({ "a": 1, "b": [1, 2], "c": "x" }),
+            &json!({ "a": 2, "b": [1], "d": true, "c": "x" }),
+            &mut differences,
+        );
+
+        assert_eq!(differences, vec!["~ /a: 1 -> 2", "- /b/1: 2", "+ /d: true"]);
+    }
+
+    #[test]
+    fn test_snapshot_is_stored_and_compared() {
+        let dir = std::env::temp_dir().join(format!("cannon-snapshots-{}", std::process::id()));
+        let snapshots = Snapshots {
+            dir: dir.clone(),
+            update: false,
+        };
+        let entry = SnapshotEntry::Enabled(true);
+        let headers = HeaderMap::new();
+        let check = |body: &[u8]| {
+            snapshots
+                .check(
+                    "get user #1",
+                    &entry,
+                    StatusCode::OK,
+                    &headers,
+                    body,
+                    &Secrets::default(),
+                )
+                .unwrap()
+        };
+
+        let stored = check(br#"{"id": 1}"#);
+        let file_exists = dir.join("get_user__1.json").exists();
+        let matched = check(br#"{"id": 1}"#);
+        let changed = check(br#"{"id": 2}"#);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(stored[0].0 && stored[0].1.starts_with("Snapshot stored"));
+        assert!(file_exists);
+        assert_eq!(matched, vec![(true, "Response matches snapshot".into())]);
+        assert_eq!(
+            changed,
+            vec![(false, "Snapshot mismatch ~ /body/id: 1 -> 2".into())]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::app::template::random::Random;
+use crate::configuration::manifest::Locale;
+use fake::faker::address::raw::{BuildingNumber, CityName, CountryName, PostCode, StreetName};
+use fake::faker::company::raw::CompanyName;
+use fake::faker::impls::address::CityNameGenFn;
+use fake::faker::internet::raw::{SafeEmail, Username};
+use fake::faker::lorem::raw::Words;
+use fake::faker::name::raw::{FirstName, LastName, Name};
+use fake::faker::phone_number::raw::PhoneNumber;
+use fake::locales::*;
+use fake::Fake;
+use liquid::model::Value as LqValue;
+use liquid::Object;
+use rand::rngs::StdRng;
+
+/// Kinds of fake data available as `fake.<kind>` variables and `{% fake <kind> %}` tag
+pub const KINDS: &[&str] = &[
+    "email",
+    "username",
+    "name",
+    "first_name",
+    "last_name",
+    "phone",
+    "address",
+    "city",
+    "country",
+    "company",
+    "lorem",
+];
+
+/// Number of words generated by `lorem` when count is not set
+pub const LOREM_WORDS: usize = 10;
+
+/// Generates fake value of kind in locale, `count` is number of words of `lorem`
+pub fn generate(random: &Random, locale: Locale, kind: &str, count: usize) -> Option<String> {
+    random.with_rng(|rng| match locale {
+        Locale::En => generate_in(EN, kind, count, rng),
+        Locale::FrFr => generate_in(FR_FR, kind, count, rng),
+        Locale::DeDe => generate_in(DE_DE, kind, count, rng),
+        Locale::ItIt => generate_in(IT_IT, kind, count, rng),
+        Locale::PtBr => generate_in(PT_BR, kind, count, rng),
+        Locale::PtPt => generate_in(PT_PT, kind, count, rng),
+        Locale::NlNl => generate_in(NL_NL, kind, count, rng),
+        Locale::TrTr => generate_in(TR_TR, kind, count, rng),
+        Locale::JaJp => generate_in(JA_JP, kind, count, rng),
+        Locale::ZhCn => generate_in(ZH_CN, kind, count, rng),
+        Locale::ZhTw => generate_in(ZH_TW, kind, count, rng),
+        Locale::ArSa => generate_in(AR_SA, kind, count, rng),
+        Locale::FaIr => generate_in(FA_IR, kind, count, rng),
+        Locale::CyGb => generate_in(CY_GB, kind, count, rng),
+    })
+}
+
+/// Builds `fake` object with freshly generated value of every kind
+pub fn object(random: &Random, locale: Locale) -> Object {
+    KINDS
+        .iter()
+        .filter_map(|kind| {
+            generate(random, locale, kind, LOREM_WORDS)
+                .map(|value| ((*kind).into(), LqValue::scalar(value)))
+        })
+        .collect()
+}
+
+fn generate_in<L: Data + CityNameGenFn + Copy>(
+    locale: L,
+    kind: &str,
+    count: usize,
+    rng: &mut StdRng,
+) -> Option<String> {
+    let value: String = match kind {
+        "email" => SafeEmail(locale).fake_with_rng(rng),
+        "username" => Username(locale).fake_with_rng(rng),
+        "name" => Name(locale).fake_with_rng(rng),
+        "first_name" => FirstName(locale).fake_with_rng(rng),
+        "last_name" => LastName(locale).fake_with_rng(rng),
+        "phone" => PhoneNumber(locale).fake_with_rng(rng),
+        "address" => format!(
+            "{} {}, {}, {}",
+            BuildingNumber(locale).fake_with_rng::<String, _>(rng),
+            StreetName(locale).fake_with_rng::<String, _>(rng),
+            CityName(locale).fake_with_rng::<String, _>(rng),
+            PostCode(locale).fake_with_rng::<String, _>(rng)
+        ),
+        "city" => CityName(locale).fake_with_rng(rng),
+        "country" => CountryName(locale).fake_with_rng(rng),
+        "company" => CompanyName(locale).fake_with_rng(rng),
+        "lorem" => Words(locale, count..count + 1)
+            .fake_with_rng::<Vec<String>, _>(rng)
+            .join(" "),
+        _ => return None,
+    };
+    Some(value)
+}
@@ -0,0 +1,313 @@
+use chrono::{DateTime, Duration, SecondsFormat, TimeZone, Utc};
+use hmac::{Hmac, KeyInit, Mac};
+use liquid_core::{
+    Display_filter, Filter, FilterParameters, FilterReflection, FromFilterParameters, ParseFilter,
+};
+use liquid_core::{Error, Expression, Result, Runtime};
+use liquid_core::{Value, ValueView};
+use percent_encoding::AsciiSet;
+use sha2::{Digest, Sha256 as Sha256Hash};
+
+/// Characters allowed in path segment besides alphanumeric ones
+const PATH_SEGMENT: &AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, FilterParameters)]
+struct DateAddArgs {
+    #[parameter(
+        description = "Offset in seconds or duration like `1d2h`, `-30m`. Units: s, m, h, d, w."
+    )]
+    offset: Expression,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "date_add",
+    description = "Shifts date (`now`, RFC3339 or epoch seconds) by offset and returns it in RFC3339.",
+    parameters(DateAddArgs),
+    parsed(DateAddFilter)
+)]
+pub struct DateAdd;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "date_add"]
+struct DateAddFilter {
+    #[parameters]
+    args: DateAddArgs,
+}
+
+impl Filter for DateAddFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &Runtime<'_>) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let offset = parse_duration(&args.offset)?;
+        let date = parse_date(input)?
+            .checked_add_signed(offset)
+            .ok_or_else(|| Error::with_msg("Date is out of range after adding offset"))?;
+        Ok(Value::scalar(to_rfc3339(date)))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "to_epoch",
+    description = "Converts date (`now`, RFC3339 or epoch seconds) into seconds since epoch.",
+    parsed(ToEpochFilter)
+)]
+pub struct ToEpoch;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "to_epoch"]
+struct ToEpochFilter;
+
+impl Filter for ToEpochFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        Ok(Value::scalar(parse_date(input)?.timestamp()))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "to_rfc3339",
+    description = "Converts date (`now`, RFC3339 or epoch seconds) into RFC3339.",
+    parsed(ToRfc3339Filter)
+)]
+pub struct ToRfc3339;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "to_rfc3339"]
+struct ToRfc3339Filter;
+
+impl Filter for ToRfc3339Filter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        Ok(Value::scalar(to_rfc3339(parse_date(input)?)))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base64_encode",
+    description = "Encodes string with base64.",
+    parsed(Base64EncodeFilter)
+)]
+pub struct Base64Encode;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "base64_encode"]
+struct Base64EncodeFilter;
+
+impl Filter for Base64EncodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        Ok(Value::scalar(base64::encode(input.to_kstr().as_bytes())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base64_decode",
+    description = "Decodes base64 string.",
+    parsed(Base64DecodeFilter)
+)]
+pub struct Base64Decode;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "base64_decode"]
+struct Base64DecodeFilter;
+
+impl Filter for Base64DecodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        let decoded = base64::decode(input.to_kstr().as_bytes())
+            .map_err(|e| Error::with_msg(format!("Invalid base64: {}", e)))?;
+        let decoded = String::from_utf8(decoded)
+            .map_err(|_| Error::with_msg("Decoded base64 is not UTF-8 string"))?;
+        Ok(Value::scalar(decoded))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_encode_path",
+    description = "Percent-encodes every segment of path keeping `/` separators.",
+    parsed(UrlEncodePathFilter)
+)]
+pub struct UrlEncodePath;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "url_encode_path"]
+struct UrlEncodePathFilter;
+
+impl Filter for UrlEncodePathFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        let path = input.to_kstr();
+        let encoded: Vec<String> = path
+            .split('/')
+            .map(|segment| percent_encoding::utf8_percent_encode(segment, PATH_SEGMENT).collect())
+            .collect();
+        Ok(Value::scalar(encoded.join("/")))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sha256",
+    description = "Returns hex encoded SHA-256 digest of string.",
+    parsed(Sha256Filter)
+)]
+pub struct Sha256;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "sha256"]
+struct Sha256Filter;
+
+impl Filter for Sha256Filter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        Ok(Value::scalar(hex::encode(Sha256Hash::digest(
+            input.to_kstr().as_bytes(),
+        ))))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "md5",
+    description = "Returns hex encoded MD5 digest of string.",
+    parsed(Md5Filter)
+)]
+pub struct Md5;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "md5"]
+struct Md5Filter;
+
+impl Filter for Md5Filter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        Ok(Value::scalar(hex::encode(md5::Md5::digest(
+            input.to_kstr().as_bytes(),
+        ))))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct HmacSha256Args {
+    #[parameter(description = "The secret key.", arg_type = "str")]
+    key: Expression,
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hmac_sha256",
+    description = "Returns hex encoded HMAC-SHA256 of string signed with key.",
+    parameters(HmacSha256Args),
+    parsed(HmacSha256Filter)
+)]
+pub struct HmacSha256;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "hmac_sha256"]
+struct HmacSha256Filter {
+    #[parameters]
+    args: HmacSha256Args,
+}
+
+impl Filter for HmacSha256Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &Runtime<'_>) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let mut mac = Hmac::<Sha256Hash>::new_from_slice(args.key.as_bytes())
+            .map_err(|e| Error::with_msg(e.to_string()))?;
+        mac.update(input.to_kstr().as_bytes());
+        Ok(Value::scalar(hex::encode(mac.finalize().into_bytes())))
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "json",
+    description = "Serializes value into JSON.",
+    parsed(JsonFilter)
+)]
+pub struct Json;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "json"]
+struct JsonFilter;
+
+impl Filter for JsonFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &Runtime<'_>) -> Result<Value> {
+        // round trip through serde_json value keeps keys of objects sorted
+        serde_json::to_value(input.to_value())
+            .and_then(|value| serde_json::to_string(&value))
+            .map(Value::scalar)
+            .map_err(|e| Error::with_msg(e.to_string()))
+    }
+}
+
+fn parse_date(input: &dyn ValueView) -> Result<DateTime<Utc>> {
+    let scalar = input
+        .as_scalar()
+        .ok_or_else(|| Error::with_msg(format!("Expected date but got {}", input.type_name())))?;
+    if let Some(seconds) = scalar.to_integer() {
+        return Utc
+            .timestamp_opt(seconds, 0)
+            .single()
+            .ok_or_else(|| Error::with_msg(format!("Date {} is out of range", seconds)));
+    }
+    let text = scalar.to_kstr();
+    if text.as_str() == "now" {
+        return Ok(Utc::now());
+    }
+    DateTime::parse_from_rfc3339(text.as_str())
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| Error::with_msg(format!("Invalid date '{}': {}", text, e)))
+}
+
+fn parse_duration(offset: &dyn ValueView) -> Result<Duration> {
+    let text = offset.to_kstr();
+    let invalid = || Error::with_msg(format!("Invalid duration '{}'", text));
+    if let Some(seconds) = offset.as_scalar().and_then(|scalar| scalar.to_integer()) {
+        return duration(seconds, 1).ok_or_else(invalid);
+    }
+    let (sign, units) = match text.strip_prefix('-') {
+        Some(units) => (-1, units),
+        None => (1, text.strip_prefix('+').unwrap_or(&text)),
+    };
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in units.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount: i64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            'w' => 604_800,
+            _ => return Err(invalid()),
+        };
+        total = duration(amount, unit)
+            .and_then(|duration| total.checked_add(&duration))
+            .ok_or_else(invalid)?;
+    }
+    if !number.is_empty() || units.is_empty() {
+        return Err(invalid());
+    }
+    Ok(total * sign)
+}
+
+/// Builds duration of amount of units in seconds, `None` when it is out of range of duration
+fn duration(amount: i64, unit: i64) -> Option<Duration> {
+    amount
+        .checked_mul(unit)
+        // Duration is stored with millisecond precision at least, bigger value panics
+        .filter(|seconds| seconds.checked_mul(1_000).is_some())
+        .map(Duration::seconds)
+}
+
+fn to_rfc3339(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,317 @@
+use crate::configuration::manifest::{Locale, PipelineEntry, Templates};
+use liquid::model::Value;
+use liquid::{Object, Parser, Template};
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+pub mod fake;
+mod filters;
+mod random;
+mod tags;
+
+pub use self::random::Random;
+
+/// Builds liquid parser with standard library and cannon-specific filters and tags,
+/// generated data comes from given random generator and fake data is in given locale
+pub fn parser(random: &Random, locale: Locale) -> Parser {
+    liquid::ParserBuilder::with_stdlib()
+        .tag(tags::FakeTag::new(random.clone(), locale))
+        .tag(tags::UuidTag::new(random.clone()))
+        .tag(tags::NowTag)
+        .tag(tags::RandomIntTag::new(random.clone()))
+        .tag(tags::RandomStringTag::new(random.clone()))
+        .filter(filters::DateAdd)
+        .filter(filters::ToEpoch)
+        .filter(filters::ToRfc3339)
+        .filter(filters::Base64Encode)
+        .filter(filters::Base64Decode)
+        .filter(filters::UrlEncodePath)
+        .filter(filters::Sha256)
+        .filter(filters::Md5)
+        .filter(filters::HmacSha256)
+        .filter(filters::Json)
+        .build()
+        .unwrap()
+}
+
+/// Compiles templates of request, headers and text body of entry
+pub fn compile(parser: &Parser, entry: &PipelineEntry, body: Option<&str>) -> Templates {
+    let compile = |template: &str| parser.parse(template).map_err(|e| e.to_string());
+    Templates {
+        request: compile(&entry.request),
+        headers: entry
+            .headers
+            .iter()
+            .map(|(key, value)| (key.clone(), compile(value)))
+            .collect(),
+        body: body.map(compile),
+    }
+}
+
+/// Renders template, in strict mode undefined variable fails rendering,
+/// otherwise it is defined as nil and rendered as empty string.
+pub fn render(template: &Template, context: &Object, strict: bool) -> Result<String, String> {
+    let mut context = Cow::Borrowed(context);
+    loop {
+        let error = match template.render(context.as_ref()) {
+            Ok(rendered) => return Ok(rendered),
+            Err(e) => e,
+        };
+        match undefined(&error) {
+            Some(path) if !strict && define_nil(context.to_mut(), &path) => {
+                debug!(
+                    "Undefined variable '{}' is rendered as empty",
+                    path.join(".")
+                )
+            }
+            Some(path) => return Err(format!("Undefined variable '{}'", path.join("."))),
+            None => return Err(error.to_string()),
+        }
+    }
+}
+
+/// Returns names of top-level variables which template source may reference,
+/// names of filters, tags and keywords are returned too.
+pub fn variables(source: &str) -> HashSet<String> {
+    let markup = Regex::new(r"(?s)\{\{.*?\}\}|\{%.*?%\}").unwrap();
+    let quoted = Regex::new(r#""[^"]*"|'[^']*'"#).unwrap();
+    let name = Regex::new(r"(?:^|[^\w.-])([A-Za-z_][\w-]*)").unwrap();
+    markup
+        .find_iter(source)
+        .flat_map(|markup| {
+            let markup = quoted.replace_all(markup.as_str(), "''").into_owned();
+            name.captures_iter(&markup)
+                .map(|captures| captures[1].to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Returns path of undefined variable or index which caused rendering error
+fn undefined(error: &liquid::Error) -> Option<Vec<String>> {
+    let text = error.to_string();
+    let mut lines = text.lines();
+    let message = lines.next()?;
+    let context: HashMap<&str, &str> = lines
+        .filter_map(|line| line.trim().split_once('='))
+        .collect();
+    if message.ends_with("Unknown variable") {
+        let name = context.get("requested variable")?;
+        Some(vec![name.to_string()])
+    } else if message.ends_with("Unknown index") {
+        let mut path: Vec<String> = context
+            .get("variable")?
+            .split('.')
+            .map(String::from)
+            .collect();
+        path.push(context.get("requested index")?.to_string());
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Defines nil value by path, variable which is defined already or isn't inside an object can't be defined,
+/// nil parents become objects
+fn define_nil(context: &mut Object, path: &[String]) -> bool {
+    let (name, parents) = match path.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    let mut object = context;
+    for parent in parents {
+        let parent = match object.get_mut(parent.as_str()) {
+            // Parent defined as nil before is undefined too, so it becomes object
+            Some(parent @ Value::Nil) => {
+                *parent = Value::Object(Object::new());
+                parent
+            }
+            Some(parent) => parent,
+            None => return false,
+        };
+        match parent {
+            Value::Object(child) => object = child,
+            _ => return false,
+        }
+    }
+    if object.contains_key(name.as_str()) {
+        return false;
+    }
+    object.insert(name.clone().into(), Value::Nil);
+    true
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use liquid::object;
+
+    fn render(template: &str) -> String {
+        let globals =
+            object!({ "user": { "name": "admin", "roles": ["a", "b"] }, "key": "secret" });
+        parser(&Random::default(), Locale::default())
+            .parse(template)
+            .unwrap()
+            .render(&globals)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_undefined_variables_rendered_as_empty_unless_strict() {
+        let parser = parser(&Random::default(), Locale::default());
+        let globals = object!({ "user": { "name": "admin" }, "ids": [1] });
+        let template = parser
+            .parse("{{ missing }}|{{ user.name }}|{{ user.email | default: 'none' }}|{{ user.address.city }}")
+            .unwrap();
+        let in_array = parser.parse("{{ ids[0].id }}").unwrap();
+
+        assert_eq!(
+            super::render(&template, &globals, false),
+            Ok("|admin|none|".to_string())
+        );
+        assert_eq!(
+            super::render(&template, &globals, true),
+            Err("Undefined variable 'missing'".to_string())
+        );
+        let strict = parser.parse("{{ user.name }}{{ user.email }}").unwrap();
+        assert_eq!(
+            super::render(&strict, &globals, true),
+            Err("Undefined variable 'user.email'".to_string())
+        );
+        assert!(super::render(&in_array, &globals, false).is_err());
+    }
+
+    #[test]
+    fn test_variables_referenced_by_template() {
+        let variables = variables(
+            "{{ host }}/users/{{ user.id }}?q={{ 'token' | append: suffix }}{% if admin %}&all{% endif %}",
+        );
+
+        assert!(variables.contains("host"));
+        assert!(variables.contains("user"));
+        assert!(variables.contains("suffix"));
+        assert!(variables.contains("admin"));
+        assert!(!variables.contains("id"));
+        assert!(!variables.contains("token"));
+        assert!(!variables.contains("users"));
+    }
+
+    #[test]
+    fn test_generator_tags() {
+        let uuid = render("{% uuid %}");
+        let number: i64 = render("{% random_int 5 7 %}").parse().unwrap();
+        let epoch: i64 = render("{% now epoch %}").parse().unwrap();
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!((5..=7).contains(&number));
+        assert_eq!(render("{% random_string 12 %}").len(), 12);
+        assert!(render("{% now %}").ends_with('Z'));
+        assert!(epoch > 1_600_000_000);
+    }
+
+    #[test]
+    fn test_fake_data_is_reproducible_with_seed() {
+        let template =
+            "{% fake name %}|{% fake email %}|{% fake lorem 3 %}|{% uuid %}|{{ fake.phone }}";
+        let render_seeded = || {
+            let random = Random::default();
+            random.seed(42);
+            let globals = liquid::object!({ "fake": fake::object(&random, Locale::default()) });
+            parser(&random, Locale::default())
+                .parse(template)
+                .unwrap()
+                .render(&globals)
+                .unwrap()
+        };
+
+        let first = render_seeded();
+        let second = render_seeded();
+        let parts: Vec<&str> = first.split('|').collect();
+
+        assert_eq!(first, second);
+        assert!(parts[1].contains('@'));
+        assert_eq!(parts[2].split(' ').count(), 3);
+        assert!(parser(&Random::default(), Locale::default())
+            .parse("{% fake unknown %}")
+            .is_err());
+        assert!(parser(&Random::default(), Locale::default())
+            .parse("{% fake email 3 %}")
+            .is_err());
+    }
+
+    #[test]
+    fn test_date_filters() {
+        assert_eq!(
+            render(r#"{{ "2020-01-01T00:00:00Z" | date_add: "1d2h" }}"#),
+            "2020-01-02T02:00:00Z"
+        );
+        assert_eq!(
+            render(r#"{{ "2020-01-01T00:00:00+02:00" | date_add: -60 }}"#),
+            "2019-12-31T21:59:00Z"
+        );
+        assert_eq!(
+            render(r#"{{ "2020-01-01T00:00:00Z" | to_epoch }}"#),
+            "1577836800"
+        );
+        assert_eq!(
+            render("{{ 1577836800 | to_rfc3339 }}"),
+            "2020-01-01T00:00:00Z"
+        );
+        assert!(parser(&Random::default(), Locale::default())
+            .parse(r#"{{ "now" | date_add: "1y" }}"#)
+            .unwrap()
+            .render(&liquid::Object::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_date_filters_out_of_range() {
+        for source in &[
+            "{{ 99999999999999 | to_rfc3339 }}",
+            r#"{{ "now" | date_add: "99999999999w" }}"#,
+            r#"{{ "now" | date_add: 9223372036854775807 }}"#,
+            r#"{{ "2020-01-01T00:00:00Z" | date_add: "9000000000000000s" }}"#,
+        ] {
+            assert!(parser(&Random::default(), Locale::default())
+                .parse(source)
+                .unwrap()
+                .render(&liquid::Object::new())
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_encoding_and_hashing_filters() {
+        assert_eq!(
+            render(r#"{{ "admin:pass" | base64_encode }}"#),
+            "YWRtaW46cGFzcw=="
+        );
+        assert_eq!(
+            render(r#"{{ "YWRtaW46cGFzcw==" | base64_decode }}"#),
+            "admin:pass"
+        );
+        assert_eq!(
+            render(r#"{{ "a b/c?d" | url_encode_path }}"#),
+            "a%20b/c%3Fd"
+        );
+        assert_eq!(
+            render(r#"{{ "abc" | sha256 }}"#),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            render(r#"{{ "abc" | md5 }}"#),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            render(r#"{{ "The quick brown fox jumps over the lazy dog" | hmac_sha256: "key" }}"#),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert_eq!(
+            render("{{ user | json }}"),
+            r#"{"name":"admin","roles":["a","b"]}"#
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::{Arc, Mutex};
+
+/// Random generator shared by templates of a pipeline, seeding it with the same
+/// value makes generated data of the run reproducible.
+#[derive(Clone, Debug)]
+pub struct Random(Arc<Mutex<StdRng>>);
+
+impl Default for Random {
+    fn default() -> Self {
+        Random(Arc::new(Mutex::new(rand::make_rng())))
+    }
+}
+
+impl Random {
+    pub fn seed(&self, seed: u64) {
+        *self.0.lock().unwrap() = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn with_rng<T>(&self, f: impl FnOnce(&mut StdRng) -> T) -> T {
+        f(&mut self.0.lock().unwrap())
+    }
+}
@@ -0,0 +1,341 @@
+use crate::app::template::fake;
+use crate::app::template::random::Random;
+use crate::configuration::manifest::Locale;
+use chrono::{SecondsFormat, Utc};
+use liquid_core::error::ResultLiquidReplaceExt;
+use liquid_core::{Expression, Language, Renderable, Result, Runtime};
+use liquid_core::{ParseTag, TagReflection, TagTokenIter, ValueView};
+use rand::distr::Alphanumeric;
+use rand::RngExt;
+use std::io::Write;
+use uuid::Builder;
+
+#[derive(Debug)]
+struct RandomUuid {
+    random: Random,
+}
+
+impl Renderable for RandomUuid {
+    fn render_to(&self, writer: &mut dyn Write, _runtime: &mut Runtime<'_>) -> Result<()> {
+        let uuid = self
+            .random
+            .with_rng(|rng| Builder::from_random_bytes(rng.random()).into_uuid());
+        write!(writer, "{}", uuid).replace("Failed to render")
+    }
+}
+
+/// `{% uuid %}` renders random UUID v4
+#[derive(Clone, Debug)]
+pub struct UuidTag {
+    random: Random,
+}
+
+impl UuidTag {
+    pub fn new(random: Random) -> Self {
+        UuidTag { random }
+    }
+}
+
+impl TagReflection for UuidTag {
+    fn tag(&self) -> &'static str {
+        "uuid"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renders random UUID v4"
+    }
+}
+
+impl ParseTag for UuidTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        arguments.expect_nothing()?;
+        Ok(Box::new(RandomUuid {
+            random: self.random.clone(),
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Now {
+    generate: fn() -> String,
+}
+
+impl Renderable for Now {
+    fn render_to(&self, writer: &mut dyn Write, _runtime: &mut Runtime<'_>) -> Result<()> {
+        write!(writer, "{}", (self.generate)()).replace("Failed to render")
+    }
+}
+
+/// `{% now %}` renders current time in RFC3339, `{% now epoch %}` in seconds since epoch
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NowTag;
+
+impl TagReflection for NowTag {
+    fn tag(&self) -> &'static str {
+        "now"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renders current time in RFC3339 or epoch form"
+    }
+}
+
+impl ParseTag for NowTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let generate: fn() -> String = match arguments.next() {
+            None => || Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            Some(token) => match token.expect_identifier().into_result()? {
+                "rfc3339" => || Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                "epoch" => || Utc::now().timestamp().to_string(),
+                "epoch_millis" => || Utc::now().timestamp_millis().to_string(),
+                _ => {
+                    return Err(
+                        arguments.raise_error("Expected one of rfc3339, epoch, epoch_millis form")
+                    )
+                }
+            },
+        };
+        arguments.expect_nothing()?;
+        Ok(Box::new(Now { generate }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct RandomInt {
+    random: Random,
+    from: Expression,
+    to: Expression,
+}
+
+impl Renderable for RandomInt {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &mut Runtime<'_>) -> Result<()> {
+        let from = evaluate_integer(&self.from, runtime)?;
+        let to = evaluate_integer(&self.to, runtime)?;
+        if from > to {
+            return Err(liquid_core::Error::with_msg(format!(
+                "Invalid random_int range {}..{}",
+                from, to
+            )));
+        }
+        let value = self.random.with_rng(|rng| rng.random_range(from..=to));
+        write!(writer, "{}", value).replace("Failed to render")
+    }
+}
+
+/// `{% random_int 1 100 %}` renders random integer in inclusive range
+#[derive(Clone, Debug)]
+pub struct RandomIntTag {
+    random: Random,
+}
+
+impl RandomIntTag {
+    pub fn new(random: Random) -> Self {
+        RandomIntTag { random }
+    }
+}
+
+impl TagReflection for RandomIntTag {
+    fn tag(&self) -> &'static str {
+        "random_int"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renders random integer in inclusive range"
+    }
+}
+
+impl ParseTag for RandomIntTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let from = arguments
+            .expect_next("Range start expected.")?
+            .expect_value()
+            .into_result()?;
+        let to = arguments
+            .expect_next("Range end expected.")?
+            .expect_value()
+            .into_result()?;
+        arguments.expect_nothing()?;
+        Ok(Box::new(RandomInt {
+            random: self.random.clone(),
+            from,
+            to,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct RandomString {
+    random: Random,
+    length: Expression,
+}
+
+impl Renderable for RandomString {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &mut Runtime<'_>) -> Result<()> {
+        let length = evaluate_integer(&self.length, runtime)?;
+        let value: String = self.random.with_rng(|rng| {
+            rng.sample_iter(Alphanumeric)
+                .take(length.max(0) as usize)
+                .map(char::from)
+                .collect()
+        });
+        write!(writer, "{}", value).replace("Failed to render")
+    }
+}
+
+/// `{% random_string 16 %}` renders random alphanumeric string of given length
+#[derive(Clone, Debug)]
+pub struct RandomStringTag {
+    random: Random,
+}
+
+impl RandomStringTag {
+    pub fn new(random: Random) -> Self {
+        RandomStringTag { random }
+    }
+}
+
+impl TagReflection for RandomStringTag {
+    fn tag(&self) -> &'static str {
+        "random_string"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renders random alphanumeric string of given length"
+    }
+}
+
+impl ParseTag for RandomStringTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let length = arguments
+            .expect_next("Length expected.")?
+            .expect_value()
+            .into_result()?;
+        arguments.expect_nothing()?;
+        Ok(Box::new(RandomString {
+            random: self.random.clone(),
+            length,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Fake {
+    random: Random,
+    locale: Locale,
+    kind: String,
+    count: Option<Expression>,
+}
+
+impl Renderable for Fake {
+    fn render_to(&self, writer: &mut dyn Write, runtime: &mut Runtime<'_>) -> Result<()> {
+        let count = match &self.count {
+            Some(count) => evaluate_integer(count, runtime)?.max(0) as usize,
+            None => fake::LOREM_WORDS,
+        };
+        let value =
+            fake::generate(&self.random, self.locale, &self.kind, count).unwrap_or_default();
+        write!(writer, "{}", value).replace("Failed to render")
+    }
+}
+
+/// `{% fake email %}` renders fake data of kind, `{% fake lorem 20 %}` renders given number of words
+#[derive(Clone, Debug)]
+pub struct FakeTag {
+    random: Random,
+    locale: Locale,
+}
+
+impl FakeTag {
+    pub fn new(random: Random, locale: Locale) -> Self {
+        FakeTag { random, locale }
+    }
+}
+
+impl TagReflection for FakeTag {
+    fn tag(&self) -> &'static str {
+        "fake"
+    }
+
+    fn description(&self) -> &'static str {
+        "Renders fake data like email, name, phone, address or lorem"
+    }
+}
+
+impl ParseTag for FakeTag {
+    fn parse(
+        &self,
+        mut arguments: TagTokenIter<'_>,
+        _options: &Language,
+    ) -> Result<Box<dyn Renderable>> {
+        let kind = arguments
+            .expect_next("Kind of fake data expected.")?
+            .expect_identifier()
+            .into_result()?
+            .to_string();
+        if !fake::KINDS.contains(&kind.as_str()) {
+            return Err(arguments.raise_error(&format!(
+                "Unknown kind of fake data '{}', expected one of {}",
+                kind,
+                fake::KINDS.join(", ")
+            )));
+        }
+        let count = match arguments.next() {
+            Some(token) if kind == "lorem" => Some(token.expect_value().into_result()?),
+            Some(token) => return Err(token.raise_custom_error("Only lorem accepts count")),
+            None => None,
+        };
+        arguments.expect_nothing()?;
+        Ok(Box::new(Fake {
+            random: self.random.clone(),
+            locale: self.locale,
+            kind,
+            count,
+        }))
+    }
+
+    fn reflection(&self) -> &dyn TagReflection {
+        self
+    }
+}
+
+fn evaluate_integer(expression: &Expression, runtime: &Runtime<'_>) -> Result<i64> {
+    let value = expression.evaluate(runtime)?;
+    value
+        .as_scalar()
+        .and_then(|scalar| scalar.to_integer())
+        .ok_or_else(|| {
+            liquid_core::Error::with_msg(format!("Expected integer but got {}", value.source()))
+        })
+}
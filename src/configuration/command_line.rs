@@ -1,35 +1,128 @@
 use crate::configuration::constants::cargo_env::CARGO_PKG_NAME;
 use clap::arg_enum;
 use log::LevelFilter;
+use regex::Regex;
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::StructOpt;
 
 arg_enum! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy)]
     pub enum LogLevel {
         Off, Error, Warn, Info, Debug, Trace,
     }
 }
 
+arg_enum! {
+    #[derive(Debug, PartialEq)]
+    pub enum ReportKind {
+        Junit,
+    }
+}
+
+#[derive(Debug)]
+pub struct ReportOutput {
+    pub kind: ReportKind,
+    pub path: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Checks manifest, its templates, selectors and regular expressions without running it
+    Validate {
+        /// Manifest file to check
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+    /// Runs every manifest found by paths, directories and glob patterns as one suite
+    Run {
+        /// Manifest files, directories searched for manifests recursively or glob patterns like `tests/**/*.yaml`
+        #[structopt(required = true)]
+        paths: Vec<String>,
+
+        /// Maximum number of manifests running concurrently
+        #[structopt(long, short = "j", default_value = "1")]
+        jobs: usize,
+
+        /// Manifest which runs before the others, its captured variables are shared with the rest of suite
+        #[structopt(long, parse(from_os_str), number_of_values = 1)]
+        setup: Vec<PathBuf>,
+    },
+}
+
 #[derive(StructOpt, Debug)]
 #[structopt(name = CARGO_PKG_NAME)]
 pub struct Opt {
     /// Set a custom configuration file. Supported: YAML, JSON, TOML, HJSON
     #[structopt(parse(from_os_str))]
-    pub file: PathBuf,
+    pub file: Option<PathBuf>,
 
     /// Sets a logging level
-    #[structopt(case_insensitive = true, long, short = "L", possible_values = &LogLevel::variants(), env = "LOG_LEVEL")]
+    #[structopt(case_insensitive = true, long, short = "L", global = true, possible_values = &LogLevel::variants(), env = "LOG_LEVEL")]
     pub logging: Option<LogLevel>,
 
     /// FIle to which application will write logs
-    #[structopt(long, short = "O", env = "LOG_OUTPUT_FILE")]
+    #[structopt(long, short = "O", env = "LOG_OUTPUT_FILE", global = true)]
     pub log_output_file: Option<PathBuf>,
+
+    /// Writes run report in format <kind>=<path>, e.g. junit=report.xml. Supported kinds: junit
+    #[structopt(long, short = "R", number_of_values = 1, global = true)]
+    pub report: Vec<ReportOutput>,
+
+    /// Fails step when its template references undefined variable instead of rendering it as empty
+    #[structopt(long, global = true)]
+    pub strict: bool,
+
+    /// Rewrites stored response snapshots instead of comparing with them
+    #[structopt(long, global = true)]
+    pub update_snapshots: bool,
+
+    /// Seed of random generator used for fake and random data, allows to replay run exactly
+    #[structopt(long, env = "CANNON_SEED", global = true)]
+    pub seed: Option<u64>,
+
+    /// File with environment variables, by default `.env` next to the manifest is used if present
+    #[structopt(long, parse(from_os_str), global = true)]
+    pub env_file: Option<PathBuf>,
+
+    /// Runs only tests with any of given tags, tags of manifest are tags of each of its tests
+    #[structopt(long = "tag", number_of_values = 1, global = true)]
+    pub tags: Vec<String>,
+
+    /// Skips tests with any of given tags
+    #[structopt(long = "skip-tag", number_of_values = 1, global = true)]
+    pub skip_tags: Vec<String>,
+
+    /// Runs only tests which names match regular expression
+    #[structopt(long, global = true)]
+    pub grep: Option<Regex>,
+
+    /// Prints manifest with included manifests and step templates resolved instead of running it
+    #[structopt(long)]
+    pub print_resolved: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl FromStr for ReportOutput {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(kind), Some(path)) if !path.is_empty() => Ok(ReportOutput {
+                kind: kind.parse()?,
+                path: PathBuf::from(path),
+            }),
+            _ => Err(format!("Expected <kind>=<path> but got '{}'", value)),
+        }
+    }
 }
 
-impl Into<LevelFilter> for LogLevel {
-    fn into(self) -> LevelFilter {
-        match self {
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
             LogLevel::Off => LevelFilter::Off,
             LogLevel::Error => LevelFilter::Error,
             LogLevel::Warn => LevelFilter::Warn,
@@ -1,3 +1,10 @@
 pub mod cargo_env {
     pub const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
 }
+
+pub mod exit_code {
+    pub const SUCCESS: i32 = 0;
+    pub const FAILED: i32 = 1;
+    pub const ERROR: i32 = 2;
+    pub const INVALID_MANIFEST: i32 = 3;
+}
@@ -1,17 +1,339 @@
 pub mod selector {
-    use jsonpath::Selector;
+    use serde::de::Error;
     use serde::{Deserialize, Deserializer};
+    use serde_json_path::JsonPath;
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Selector, D::Error>
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<JsonPath, D::Error>
     where
         D: Deserializer<'de>,
     {
-        String::deserialize(deserializer).map(|v| Selector::new(v.as_str()).unwrap())
+        let expression = String::deserialize(deserializer)?;
+        parse(&expression).map_err(D::Error::custom)
+    }
+
+    pub(super) fn parse(expression: &str) -> Result<JsonPath, String> {
+        JsonPath::parse(expression)
+            .map_err(|e| format!("Invalid JSONPath expression '{}': {}", expression, e))
+    }
+}
+
+pub mod selectors {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use serde_json_path::JsonPath;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<JsonPath>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|expression| super::selector::parse(expression).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+pub mod jmespath {
+    use jmespath::Expression;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Expression<'static>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let expression = String::deserialize(deserializer)?;
+        jmespath::compile(&expression).map_err(|e| {
+            D::Error::custom(format!(
+                "Invalid JMESPath expression '{}': {}",
+                expression, e
+            ))
+        })
+    }
+}
+
+pub mod xpath {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use sxd_xpath::{Factory, XPath};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<XPath, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let expression = String::deserialize(deserializer)?;
+        match Factory::new().build(expression.as_str()) {
+            Ok(Some(xpath)) => Ok(xpath),
+            Ok(None) => Err(D::Error::custom("XPath expression is empty")),
+            Err(e) => Err(D::Error::custom(format!(
+                "Invalid XPath expression '{}': {}",
+                expression, e
+            ))),
+        }
+    }
+}
+
+pub mod status {
+    use crate::configuration::manifest::StatusExpectation;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+
+    #[derive(Deserialize)]
+    struct Range {
+        from: u16,
+        to: u16,
+    }
+
+    /// Parses expected status: code, list of codes, range `{from, to}` or class like `2xx`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<StatusExpectation>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let status = match Option::<Value>::deserialize(deserializer)? {
+            Some(status) => status,
+            None => return Ok(None),
+        };
+        match status {
+            Value::Number(_) => code(&status).map(StatusExpectation::Code),
+            Value::Array(codes) => codes
+                .iter()
+                .map(code)
+                .collect::<Result<_, _>>()
+                .map(StatusExpectation::Set),
+            Value::String(class) => self::class(&class).map(StatusExpectation::Class),
+            Value::Object(_) => serde_json::from_value::<Range>(status)
+                .map(|range| StatusExpectation::Range {
+                    from: range.from,
+                    to: range.to,
+                })
+                .map_err(|e| format!("Invalid status range: {}", e)),
+            status => Err(format!(
+                "Invalid status {}, expected code, list of codes, range or class like 2xx",
+                status
+            )),
+        }
+        .map(Some)
+        .map_err(D::Error::custom)
+    }
+
+    fn code(code: &Value) -> Result<u16, String> {
+        code.as_u64()
+            .filter(|code| (100..1000).contains(code))
+            .map(|code| code as u16)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid status code {}, expected number from 100 to 999",
+                    code
+                )
+            })
+    }
+
+    /// Parses status class like `2xx` into its first digit
+    fn class(class: &str) -> Result<u16, String> {
+        let bytes = class.to_lowercase().into_bytes();
+        match bytes.as_slice() {
+            [digit @ b'1'..=b'5', b'x', b'x'] => Ok(u16::from(digit - b'0')),
+            _ => Err(format!(
+                "Invalid status class '{}', expected one of 1xx, 2xx, 3xx, 4xx, 5xx",
+                class
+            )),
+        }
+    }
+}
+
+pub mod regex {
+    use regex::Regex;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        parse(&pattern).map_err(D::Error::custom)
+    }
+
+    pub(super) fn parse(pattern: &str) -> Result<Regex, String> {
+        Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))
+    }
+}
+
+pub mod matcher {
+    use crate::configuration::manifest::{MatchMode, Matcher};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+
+    /// Parses pattern of `matches`, either regex or `{pattern, mode}`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Matcher, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse(Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+
+    fn parse(matcher: Value) -> Result<Matcher, String> {
+        match matcher {
+            Value::String(pattern) => super::regex::parse(&pattern).map(Matcher::Pattern),
+            Value::Object(mut matcher) => {
+                let pattern = match matcher.remove("pattern") {
+                    Some(Value::String(pattern)) => super::regex::parse(&pattern)?,
+                    _ => return Err("Regex `pattern` of matcher expected".into()),
+                };
+                let mode = match matcher.remove("mode") {
+                    Some(mode) => serde_json::from_value::<MatchMode>(mode)
+                        .map_err(|e| format!("Invalid match mode: {}", e))?,
+                    None => MatchMode::default(),
+                };
+                Ok(Matcher::Mode { pattern, mode })
+            }
+            matcher => Err(format!(
+                "Invalid matcher {}, expected regex or object with `pattern` and `mode`",
+                matcher
+            )),
+        }
+    }
+}
+
+pub mod header_expectations {
+    use crate::configuration::manifest::HeaderExpectation;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    /// Parses expectation of each header: exact value, `matches` regex or `present` flag
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<String, HeaderExpectation>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        HashMap::<String, Value>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, expectation)| {
+                parse(expectation)
+                    .map(|expectation| (name.clone(), expectation))
+                    .map_err(|e| D::Error::custom(format!("header '{}': {}", name, e)))
+            })
+            .collect()
+    }
+
+    fn parse(expectation: Value) -> Result<HeaderExpectation, String> {
+        match expectation {
+            Value::String(value) => Ok(HeaderExpectation::Equal(value)),
+            Value::Object(mut expectation) if expectation.contains_key("matches") => {
+                match expectation.remove("matches") {
+                    Some(Value::String(pattern)) => super::regex::parse(&pattern)
+                        .map(|matches| HeaderExpectation::Matches { matches }),
+                    _ => Err("Regex of `matches` expected".into()),
+                }
+            }
+            Value::Object(expectation) if expectation.contains_key("present") => {
+                match expectation.get("present") {
+                    Some(Value::Bool(present)) => {
+                        Ok(HeaderExpectation::Present { present: *present })
+                    }
+                    _ => Err("Boolean `present` expected".into()),
+                }
+            }
+            expectation => Err(format!(
+                "Invalid expectation {}, expected value, `matches` or `present`",
+                expectation
+            )),
+        }
+    }
+}
+
+pub mod length {
+    use crate::configuration::manifest::{AssertParamValueVar, LengthExpectation};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+
+    #[derive(Deserialize)]
+    struct Range {
+        from: usize,
+        to: usize,
+    }
+
+    /// Parses expected length, either `value`/`var` or range `{from, to}`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<LengthExpectation, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let length = Value::deserialize(deserializer)?;
+        let is_range = length
+            .as_object()
+            .is_some_and(|length| length.contains_key("from") || length.contains_key("to"));
+        if is_range {
+            serde_json::from_value::<Range>(length)
+                .map(|range| LengthExpectation::Range {
+                    from: range.from,
+                    to: range.to,
+                })
+                .map_err(|e| D::Error::custom(format!("Invalid length range: {}", e)))
+        } else {
+            serde_json::from_value::<AssertParamValueVar>(length)
+                .map(LengthExpectation::Exact)
+                .map_err(|e| D::Error::custom(format!("Invalid length: {}", e)))
+        }
+    }
+}
+
+pub mod capture_source {
+    use crate::configuration::manifest::CaptureSource;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    /// Parses capture source like `body`, `status`, `headers`, `header:<name>` or `cookie:<name>`
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<CaptureSource, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+        let parts: Vec<&str> = source.splitn(2, ':').map(str::trim).collect();
+        match parts.as_slice() {
+            ["body"] => Ok(CaptureSource::Body),
+            ["status"] => Ok(CaptureSource::Status),
+            ["headers"] => Ok(CaptureSource::Headers),
+            ["header", name] if !name.is_empty() => Ok(CaptureSource::Header(name.to_string())),
+            ["cookie", name] if !name.is_empty() => Ok(CaptureSource::Cookie(name.to_string())),
+            _ => Err(D::Error::custom(format!(
+                "Invalid capture source '{}', expected one of body, status, headers, header:<name>, cookie:<name>",
+                source
+            ))),
+        }
+    }
+}
+
+pub mod optional_flatten {
+    use serde::de::{DeserializeOwned, Error};
+    use serde::{Deserialize, Deserializer};
+    use serde_json::{Map, Value};
+
+    /// Deserializes optional flattened enum. Unlike `Option` with `flatten`
+    /// it reports errors of present fields instead of ignoring them.
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        let fields = Map::<String, Value>::deserialize(deserializer)?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        T::deserialize(Value::Object(fields))
+            .map(Some)
+            .map_err(D::Error::custom)
     }
 }
 
 pub mod uri {
     use hyper::http::uri::Uri;
+    use serde::de::Error;
     use serde::{Deserialize, Deserializer};
     use std::str::FromStr;
 
@@ -19,12 +341,44 @@ pub mod uri {
     where
         D: Deserializer<'de>,
     {
-        String::deserialize(deserializer).map(|v| Uri::from_str(v.as_str()).unwrap())
+        let uri = String::deserialize(deserializer)?;
+        Uri::from_str(uri.as_str())
+            .map_err(|e| D::Error::custom(format!("Invalid URI '{}': {}", uri, e)))
+    }
+}
+
+pub mod schema {
+    use crate::configuration::manifest::SchemaEntry;
+    use hyper::http::uri::Uri;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+    use serde_json::Value;
+    use std::str::FromStr;
+
+    /// Parses JSON schema, string is URI of schema file, object or boolean is inline schema
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SchemaEntry>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Null => Ok(None),
+            Value::String(uri) => Uri::from_str(uri.as_str())
+                .map(|uri| Some(SchemaEntry::Uri(uri)))
+                .map_err(|e| D::Error::custom(format!("Invalid URI '{}': {}", uri, e))),
+            schema @ Value::Object(_) | schema @ Value::Bool(_) => {
+                Ok(Some(SchemaEntry::Inline(schema)))
+            }
+            schema => Err(D::Error::custom(format!(
+                "Invalid JSON schema {}, expected URI of schema file, object or boolean",
+                schema
+            ))),
+        }
     }
 }
 
 pub mod http_method {
     use hyper::Method;
+    use serde::de::Error;
     use serde::{Deserialize, Deserializer};
     use std::str::FromStr;
 
@@ -32,17 +386,22 @@ pub mod http_method {
     where
         D: Deserializer<'de>,
     {
-        String::deserialize(deserializer).map(|v| Method::from_str(v.as_str()).unwrap())
+        let method = String::deserialize(deserializer)?;
+        Method::from_str(method.as_str())
+            .map_err(|e| D::Error::custom(format!("Invalid HTTP method '{}': {}", method, e)))
     }
 }
 
 pub mod base64_property {
+    use serde::de::Error;
     use serde::{Deserialize, Deserializer};
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        String::deserialize(deserializer).map(|v| base64::decode(v).unwrap())
+        let data = String::deserialize(deserializer)?;
+        base64::decode(data.trim())
+            .map_err(|e| D::Error::custom(format!("Invalid base64 data: {}", e)))
     }
 }
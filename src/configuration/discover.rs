@@ -0,0 +1,115 @@
+use crate::configuration::resolve::{self, canonical};
+use glob::Pattern;
+use std::path::{Path, PathBuf};
+
+/// Extensions of manifest files searched in directories
+const EXTENSIONS: [&str; 5] = ["yaml", "yml", "json", "toml", "hjson"];
+/// Directory of stored response snapshots, it never contains manifests
+const SNAPSHOTS: &str = "__snapshots__";
+
+/// Finds manifests by paths, directories and glob patterns keeping given order.
+/// Setup manifests and manifests included by other manifests aren't returned,
+/// neither are data files found in directories, e.g. JSON schemas and request bodies.
+pub fn manifests(patterns: &[String], setup: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut found = Vec::new();
+    for pattern in patterns {
+        let matched = if Path::new(pattern).is_dir() {
+            let dir = Pattern::escape(pattern.trim_end_matches('/'));
+            expand(&format!("{}/**/*", dir))?
+                .into_iter()
+                .filter(|file| {
+                    file.extension()
+                        .is_some_and(|extension| EXTENSIONS.iter().any(|e| extension == *e))
+                })
+                .filter(|file| is_manifest(file))
+                .collect()
+        } else {
+            expand(pattern)?
+        };
+        if matched.is_empty() {
+            return Err(format!("No manifest matches '{}'", pattern));
+        }
+        found.extend(matched);
+    }
+    let mut skipped: Vec<PathBuf> = setup.iter().map(|file| canonical(file)).collect();
+    for file in found.iter().chain(setup) {
+        skipped.extend(resolve::included(file));
+    }
+    let mut manifests = Vec::new();
+    for file in found {
+        let path = canonical(&file);
+        if !skipped.contains(&path) {
+            skipped.push(path);
+            manifests.push(file);
+        }
+    }
+    Ok(manifests)
+}
+
+/// Tells if file may be a manifest, document without `pipeline` and `include` is data of other manifests.
+/// File which can't be read is kept, so its problem is reported when it's loaded.
+fn is_manifest(file: &Path) -> bool {
+    match resolve::read(file) {
+        Ok(document) if document.get("pipeline").is_none() && document.get("include").is_none() => {
+            debug!("File {} is skipped, it is not a manifest", file.display());
+            false
+        }
+        _ => true,
+    }
+}
+
+fn expand(pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let paths = glob::glob(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+    Ok(paths
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file() && !path.components().any(|c| c.as_os_str() == SNAPSHOTS))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn test_manifests_found_in_directory_without_setup_and_included_ones() {
+        let dir = env::temp_dir().join(format!("cannon-discover-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::create_dir_all(dir.join("schemas")).unwrap();
+        fs::create_dir_all(dir.join("__snapshots__/users")).unwrap();
+        fs::write(dir.join("users.yaml"), "include: shared/auth.yaml\n").unwrap();
+        fs::write(dir.join("orders.json"), r#"{"pipeline": {"test": []}}"#).unwrap();
+        fs::write(dir.join("broken.toml"), "pipeline = [").unwrap();
+        fs::write(dir.join("schemas/user.json"), r#"{"type": "object"}"#).unwrap();
+        fs::write(dir.join("setup.yaml"), "name: Setup\npipeline: {}\n").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        fs::write(dir.join("shared/auth.yaml"), "vars: {}\n").unwrap();
+        fs::write(dir.join("__snapshots__/users/Get.json"), "{}").unwrap();
+        let path = dir.to_string_lossy().into_owned();
+
+        let found = manifests(std::slice::from_ref(&path), &[dir.join("setup.yaml")]);
+        let globbed = manifests(&[format!("{}/*.yaml", path), path.clone()], &[]);
+        let missing = manifests(&[format!("{}/*.yml", path)], &[]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            found.unwrap(),
+            vec![
+                dir.join("broken.toml"),
+                dir.join("orders.json"),
+                dir.join("users.yaml")
+            ]
+        );
+        assert_eq!(
+            globbed.unwrap(),
+            vec![
+                dir.join("setup.yaml"),
+                dir.join("users.yaml"),
+                dir.join("broken.toml"),
+                dir.join("orders.json")
+            ]
+        );
+        assert!(missing.unwrap_err().starts_with("No manifest matches"));
+    }
+}
@@ -0,0 +1,183 @@
+use liquid::model::Value as LqValue;
+use liquid::Object;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+
+/// Name of file with environment variables looked up next to the manifest
+pub const ENV_FILE: &str = ".env";
+
+/// Builds `env` template variable. Defaults of manifest are overridden by variables of
+/// `.env` file, which are overridden by process environment. `.env` file is taken
+/// from `env_file` or from directory of the manifest if present.
+///
+/// Every `env.NAME` referenced by the resolved manifest, including its included manifests
+/// and step templates, must be defined, unless all its references have `default` filter,
+/// in that case missing variable is set to nil.
+pub fn load(
+    manifest_file: &Path,
+    manifest: &Value,
+    env_file: Option<&Path>,
+    defaults: &Object,
+) -> Result<Object, String> {
+    let mut variables = defaults.clone();
+    let manifest_env_file = manifest_file
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(ENV_FILE);
+    let env_file = match env_file {
+        Some(env_file) => Some(env_file),
+        None if manifest_env_file.is_file() => Some(manifest_env_file.as_path()),
+        None => None,
+    };
+    if let Some(env_file) = env_file {
+        variables.extend(read_env_file(env_file)?);
+    }
+    variables.extend(env::vars().map(|(key, value)| (key.into(), LqValue::scalar(value))));
+
+    let mut texts = Vec::new();
+    strings(manifest, &mut texts);
+    let mut missing = Vec::new();
+    for (name, has_default) in references(&texts.join("\n")) {
+        if variables.contains_key(name.as_str()) {
+            continue;
+        }
+        if has_default {
+            variables.insert(name.into(), LqValue::Nil);
+        } else {
+            missing.push(name);
+        }
+    }
+    if missing.is_empty() {
+        Ok(variables)
+    } else {
+        missing.sort();
+        Err(format!(
+            "Missing environment variables without default: {}",
+            missing.join(", ")
+        ))
+    }
+}
+
+fn read_env_file(path: &Path) -> Result<Object, String> {
+    let error = |e: dotenvy::Error| format!("Failed to load env file {}: {}", path.display(), e);
+    dotenvy::from_path_iter(path)
+        .map_err(error)?
+        .map(|item| {
+            item.map(|(key, value)| (key.into(), LqValue::scalar(value)))
+                .map_err(error)
+        })
+        .collect()
+}
+
+/// Collects strings of document, both keys and values, which may hold templates
+fn strings<'a>(value: &'a Value, texts: &mut Vec<&'a str>) {
+    match value {
+        Value::String(text) => texts.push(text),
+        Value::Array(values) => values.iter().for_each(|value| strings(value, texts)),
+        Value::Object(map) => map.iter().for_each(|(key, value)| {
+            texts.push(key);
+            strings(value, texts)
+        }),
+        _ => {}
+    }
+}
+
+/// Finds names of `env.NAME` and `env['NAME']` references in templates of the manifest,
+/// each name is paired with flag showing if all its references have `default` filter.
+fn references(manifest: &str) -> HashMap<String, bool> {
+    let reference = Regex::new(
+        r#"\benv(?:\.([A-Za-z_][A-Za-z0-9_]*)|\[\s*['"]([^'"]+)['"]\s*\])(\s*\|\s*default\s*:)?"#,
+    )
+    .unwrap();
+    let mut references = HashMap::new();
+    for captures in reference.captures_iter(manifest) {
+        let name = captures
+            .get(1)
+            .or_else(|| captures.get(2))
+            .map(|name| name.as_str().to_owned())
+            .unwrap_or_default();
+        *references.entry(name).or_insert(true) &= captures.get(3).is_some();
+    }
+    references
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::configuration::resolve;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn test_references_with_and_without_default() {
+        let references = references(
+            r#"
+            request: http://{{ env.HOST }}/{{ env['API_VERSION'] | default: "v1" }}
+            headers:
+              Authorization: Bearer {{ env.TOKEN | default: token }}
+              X-Token: {{ env.TOKEN }}
+            "#,
+        );
+
+        assert_eq!(references.get("HOST"), Some(&false));
+        assert_eq!(references.get("API_VERSION"), Some(&true));
+        assert_eq!(references.get("TOKEN"), Some(&false));
+        assert_eq!(references.len(), 3);
+    }
+
+    #[test]
+    fn test_env_file_overrides_defaults_and_missing_included_variables_are_reported() {
+        let dir = env::temp_dir().join(format!("cannon-env-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("manifest.yaml");
+        fs::write(
+            dir.join(ENV_FILE),
+            "CANNON_TEST_HOST=from-file\n# comment\nCANNON_TEST_PORT='8080'\n",
+        )
+        .unwrap();
+        let defaults: Object = vec![
+            ("CANNON_TEST_HOST".into(), LqValue::scalar("default")),
+            ("CANNON_TEST_USER".into(), LqValue::scalar("admin")),
+        ]
+        .into_iter()
+        .collect();
+
+        let document = json!({
+            "request": "http://{{ env.CANNON_TEST_HOST }}:{{ env.CANNON_TEST_PORT }}/{{ env.CANNON_TEST_PATH | default: '' }}"
+        });
+        let loaded = load(&manifest, &document, None, &defaults);
+        fs::write(
+            dir.join("included.yaml"),
+            "templates:\n  get:\n    request: http://{{ env.CANNON_TEST_MISSING }}\n",
+        )
+        .unwrap();
+        let document =
+            json!({ "include": "included.yaml", "pipeline": { "test": [{ "extends": "get" }] } });
+        let (document, _) = resolve::resolve(&manifest, document).unwrap();
+        let missing = load(&manifest, &document, None, &defaults);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(
+            loaded.get("CANNON_TEST_HOST"),
+            Some(&LqValue::scalar("from-file"))
+        );
+        assert_eq!(
+            loaded.get("CANNON_TEST_PORT"),
+            Some(&LqValue::scalar("8080"))
+        );
+        assert_eq!(
+            loaded.get("CANNON_TEST_USER"),
+            Some(&LqValue::scalar("admin"))
+        );
+        assert_eq!(loaded.get("CANNON_TEST_PATH"), Some(&LqValue::Nil));
+        assert_eq!(
+            missing.unwrap_err(),
+            "Missing environment variables without default: CANNON_TEST_MISSING"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+/// Lines of manifest fields by path like `pipeline.test[0].request`.
+/// Lines are known for YAML and JSON manifests, other formats have no lines.
+#[derive(Debug, Default)]
+pub struct Locations {
+    lines: HashMap<String, usize>,
+}
+
+#[derive(Default)]
+struct Events(Vec<(Event, usize)>);
+
+impl MarkedEventReceiver for Events {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        self.0.push((event, mark.line()));
+    }
+}
+
+impl Locations {
+    /// Reads locations of manifest file, unreadable file has no locations
+    pub fn read(file: &Path) -> Self {
+        fs::read_to_string(file)
+            .map(|text| Locations::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut events = Events::default();
+        let mut locations = Locations::default();
+        if Parser::new(text.chars()).load(&mut events, false).is_err() {
+            return locations;
+        }
+        let events = events.0;
+        let mut position = events
+            .iter()
+            .position(|(event, _)| *event == Event::DocumentStart)
+            .map_or(events.len(), |position| position + 1);
+        if position < events.len() {
+            locations.walk(&events, &mut position, String::new());
+        }
+        locations
+    }
+
+    /// Returns line of the field or of its closest parent with known line
+    pub fn line(&self, path: &str) -> Option<usize> {
+        let mut path = path;
+        loop {
+            if let Some(line) = self.lines.get(path) {
+                return Some(*line);
+            }
+            if path.is_empty() {
+                return None;
+            }
+            path = &path[..path.rfind(['.', '[']).unwrap_or(0)];
+        }
+    }
+
+    fn walk(&mut self, events: &[(Event, usize)], position: &mut usize, path: String) {
+        let (event, line) = &events[*position];
+        *position += 1;
+        if !path.is_empty() {
+            self.lines.entry(path.clone()).or_insert(*line);
+        }
+        match event {
+            Event::MappingStart(_) => {
+                while *position < events.len() && events[*position].0 != Event::MappingEnd {
+                    let child = match &events[*position] {
+                        (Event::Scalar(key, ..), line) => {
+                            let child = join(&path, key);
+                            self.lines.insert(child.clone(), *line);
+                            *position += 1;
+                            child
+                        }
+                        // Complex keys aren't used by manifest, such entries get unknown path
+                        _ => {
+                            self.walk(events, position, join(&path, "?"));
+                            join(&path, "?")
+                        }
+                    };
+                    self.walk(events, position, child);
+                }
+                *position += 1;
+            }
+            Event::SequenceStart(_) => {
+                let mut index = 0;
+                while *position < events.len() && events[*position].0 != Event::SequenceEnd {
+                    self.walk(events, position, format!("{}[{}]", path, index));
+                    index += 1;
+                }
+                *position += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Formats path of deserialization error the same way as paths of locations
+pub fn path(path: &serde_path_to_error::Path) -> String {
+    let mut formatted = String::new();
+    for segment in path.iter() {
+        match segment {
+            serde_path_to_error::Segment::Seq { index } => {
+                formatted.push_str(&format!("[{}]", index))
+            }
+            serde_path_to_error::Segment::Map { key } => formatted = join(&formatted, key),
+            serde_path_to_error::Segment::Enum { variant } => formatted = join(&formatted, variant),
+            serde_path_to_error::Segment::Unknown => break,
+        }
+    }
+    formatted
+}
+
+pub fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_lines_of_yaml_fields() {
+        let locations = Locations::parse(
+            "name: Example\npipeline:\n  test:\n    - name: First\n      request: http://localhost\n    - name: Second\n      headers:\n        X-Id: '1'\n",
+        );
+
+        assert_eq!(locations.line("name"), Some(1));
+        assert_eq!(locations.line("pipeline.test[0].request"), Some(5));
+        assert_eq!(locations.line("pipeline.test[1]"), Some(6));
+        assert_eq!(locations.line("pipeline.test[1].headers.X-Id"), Some(8));
+        assert_eq!(locations.line("pipeline.test[1].method"), Some(6));
+        assert_eq!(locations.line("vars"), None);
+    }
+
+    #[test]
+    fn test_lines_of_json_fields() {
+        let locations =
+            Locations::parse("{\n \"name\": \"Example\",\n \"pipeline\": {\n  \"test\": [\n   {\"name\": \"First\"}\n  ]\n }\n}\n");
+
+        assert_eq!(locations.line("name"), Some(2));
+        assert_eq!(locations.line("pipeline.test[0].name"), Some(5));
+    }
+}
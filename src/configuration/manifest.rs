@@ -1,16 +1,21 @@
-use config::{Config, ConfigError, File};
+use crate::configuration::location::{self, Locations};
+use crate::configuration::resolve::{self, Origin, Origins};
 use derivative::*;
 use hyper::http::uri::Uri;
 use hyper::Method;
-use jsonpath::Selector;
-use liquid::Object;
+use jmespath::Expression;
+use jsonschema::Validator;
+use liquid::{Object, Template};
 use regex::Regex;
-use serde::export::fmt::Debug;
+use serde::de::DeserializeOwned;
 use serde_derive::Deserialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::convert::TryFrom;
-use std::path::PathBuf;
+use serde_json_path::JsonPath;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use sxd_xpath::XPath;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -26,13 +31,6 @@ pub enum Code {
     Lua(SourceCode),
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-pub enum VarEntry {
-    Single(String),
-    Array(Vec<String>),
-}
-
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum BodyEntry {
@@ -42,22 +40,60 @@ pub enum BodyEntry {
     Base64(#[serde(with = "crate::configuration::deserialize::base64_property")] Vec<u8>),
 }
 
+/// JSON schema of response body, URI of schema file or inline object or boolean schema
+#[derive(Debug)]
+pub enum SchemaEntry {
+    Uri(Uri),
+    Inline(Value),
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Snapshot {
+    #[serde(default)]
+    pub status: bool,
+    #[serde(default)]
+    pub headers: Vec<String>,
+    #[serde(default, with = "crate::configuration::deserialize::selectors")]
+    pub ignore: Vec<JsonPath>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum SnapshotEntry {
+    Enabled(bool),
+    Options(Snapshot),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Csv,
+    Json,
+    Jsonl,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Resource {
     #[serde(with = "crate::configuration::deserialize::uri")]
     pub uri: Uri,
+    #[serde(default)]
+    pub format: Option<DataFormat>,
+    #[serde(default)]
+    pub step: Option<String>,
 }
 
 #[derive(Deserialize, Derivative)]
 #[serde(rename_all = "lowercase")]
 #[derivative(Debug)]
 pub enum Capture {
-    Json(
+    Json(#[serde(with = "crate::configuration::deserialize::selector")] JsonPath),
+    Regex(#[serde(with = "crate::configuration::deserialize::regex")] Regex),
+    Xpath(#[serde(with = "crate::configuration::deserialize::xpath")] XPath),
+    Jmespath(
         #[derivative(Debug = "ignore")]
-        #[serde(with = "crate::configuration::deserialize::selector")]
-        Selector,
+        #[serde(with = "crate::configuration::deserialize::jmespath")]
+        Expression<'static>,
     ),
-    Regex(#[serde(with = "serde_regex")] Regex),
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,11 +103,51 @@ pub enum AssertParamValueVar {
     Var(String),
 }
 
+#[derive(Debug)]
+pub enum LengthExpectation {
+    Exact(AssertParamValueVar),
+    Range { from: usize, to: usize },
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    Nil,
+    String,
+    Number,
+    Integer,
+    Float,
+    Boolean,
+    Array,
+    Object,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AssertFunction {
     Equal(AssertParamValueVar),
     NotEqual(AssertParamValueVar),
+    Gt(AssertParamValueVar),
+    Ge(AssertParamValueVar),
+    Lt(AssertParamValueVar),
+    Le(AssertParamValueVar),
+    Contains(AssertParamValueVar),
+    #[serde(rename = "starts_with", alias = "startswith")]
+    StartsWith(AssertParamValueVar),
+    #[serde(rename = "ends_with", alias = "endswith")]
+    EndsWith(AssertParamValueVar),
+    Length(#[serde(with = "crate::configuration::deserialize::length")] LengthExpectation),
+    #[serde(rename = "type_is", alias = "typeis")]
+    TypeIs(ValueType),
+    Exists(bool),
+    #[serde(rename = "is_nil", alias = "isnil")]
+    IsNil(bool),
+    #[serde(rename = "one_of", alias = "oneof")]
+    OneOf(AssertParamValueVar),
+    Between {
+        from: AssertParamValueVar,
+        to: AssertParamValueVar,
+    },
 }
 
 #[derive(Debug, Deserialize)]
@@ -84,26 +160,145 @@ pub enum Functor {
         #[serde(default)]
         message: Option<String>,
     },
-    Matches(#[serde(with = "serde_regex")] Regex),
+    Matches(#[serde(with = "crate::configuration::deserialize::matcher")] Matcher),
+    All(Vec<Functor>),
+    Any(Vec<Functor>),
+    Not(Box<Functor>),
+}
+
+#[derive(Debug, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    #[default]
+    Every,
+    Any,
+}
+
+#[derive(Debug)]
+pub enum Matcher {
+    Pattern(Regex),
+    Mode { pattern: Regex, mode: MatchMode },
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub enum CaptureSource {
+    #[default]
+    Body,
+    Status,
+    Headers,
+    Header(String),
+    Cookie(String),
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CaptureEntry {
-    #[serde(flatten)]
-    pub cap: Capture,
+    #[serde(
+        flatten,
+        deserialize_with = "crate::configuration::deserialize::optional_flatten::deserialize"
+    )]
+    pub cap: Option<Capture>,
+    #[serde(
+        default,
+        alias = "source",
+        with = "crate::configuration::deserialize::capture_source"
+    )]
+    pub from: CaptureSource,
     #[serde(rename = "as")]
     pub variable: String,
+    #[serde(default)]
+    pub namespaces: HashMap<String, String>,
+    #[serde(default)]
     pub on: Vec<Functor>,
+    /// Captured value is masked in logs, reports and snapshots
+    #[serde(default)]
+    pub secret: bool,
+}
+
+#[derive(Debug)]
+pub enum StatusExpectation {
+    Code(u16),
+    Set(Vec<u16>),
+    Range {
+        from: u16,
+        to: u16,
+    },
+    /// First digit of status class like `2xx`
+    Class(u16),
+}
+
+#[derive(Debug)]
+pub enum HeaderExpectation {
+    Equal(String),
+    Matches { matches: Regex },
+    Present { present: bool },
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct Expect {
+    #[serde(default, with = "crate::configuration::deserialize::status")]
+    pub status: Option<StatusExpectation>,
+    #[serde(
+        default,
+        with = "crate::configuration::deserialize::header_expectations"
+    )]
+    pub headers: HashMap<String, HeaderExpectation>,
+    /// Maximum response time in milliseconds
+    #[serde(default)]
+    pub latency: Option<u64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    #[default]
+    En,
+    FrFr,
+    DeDe,
+    ItIt,
+    PtBr,
+    PtPt,
+    NlNl,
+    TrTr,
+    JaJp,
+    ZhCn,
+    ZhTw,
+    ArSa,
+    FaIr,
+    CyGb,
 }
 
 #[derive(Debug, Deserialize, Default)]
 pub struct Manifest {
     pub name: String,
+    /// Address of results collector, it is required but not read until collectors are implemented
+    #[allow(dead_code)]
     #[serde(with = "crate::configuration::deserialize::uri")]
     pub collect: Uri,
     pub pipeline: Pipeline,
     #[serde(default)]
     pub vars: Object,
+    #[serde(default)]
+    pub resources: Vec<Resource>,
+    /// Locale of generated fake data
+    #[serde(default)]
+    pub locale: Locale,
+    /// Default values of environment variables
+    #[serde(default)]
+    pub env: Object,
+    /// Names of variables, environment variables and captures which values are masked in output
+    #[serde(default)]
+    pub secrets: HashSet<String>,
+    /// Undefined variable in template fails the step instead of being rendered as empty
+    #[serde(default)]
+    pub strict: bool,
+    /// Tags of every step of pipeline
+    #[serde(default)]
+    pub tags: HashSet<String>,
+    /// Files and paths where fields of the manifest are defined
+    #[serde(skip)]
+    pub origins: Origins,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -113,7 +308,16 @@ pub struct Pipeline {
     pub test: Vec<PipelineEntry>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Templates of entry compiled once when pipeline is created
+pub struct Templates {
+    pub request: Result<Template, String>,
+    pub headers: Vec<(String, Result<Template, String>)>,
+    /// Template of text body, binary body isn't a template
+    pub body: Option<Result<Template, String>>,
+}
+
+#[derive(Deserialize, Derivative)]
+#[derivative(Debug)]
 pub struct PipelineEntry {
     pub before: Option<Code>,
     pub after: Option<Code>,
@@ -129,28 +333,426 @@ pub struct PipelineEntry {
     pub vars: Object,
     #[serde(default)]
     pub capture: Vec<CaptureEntry>,
-    // pub vars: HashMap<String, VarEntry>,
+    #[serde(default)]
+    pub expect: Option<Expect>,
+    #[serde(default, with = "crate::configuration::deserialize::schema")]
+    pub schema: Option<SchemaEntry>,
+    #[serde(default)]
+    pub snapshot: Option<SnapshotEntry>,
+    #[serde(default)]
+    pub tags: HashSet<String>,
+    #[serde(skip)]
+    #[derivative(Debug = "ignore")]
+    pub templates: Templates,
+    /// Schema of response body compiled once when pipeline is created
+    #[serde(skip)]
+    #[derivative(Debug = "ignore")]
+    pub validator: Option<Result<Validator, String>>,
+}
+
+/// Problem of manifest with location of the field which caused it
+#[derive(Debug)]
+pub struct ManifestError {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub path: String,
+    pub message: String,
 }
 
 impl Manifest {
-    pub fn from(file: PathBuf) -> Result<Self, ConfigError> {
-        let mut config = Config::new();
-        config
-            .merge(File::from(file))
-            .expect("Error while loading configuration from file");
+    /// Reads manifest document with included manifests and step templates resolved
+    pub fn resolve(file: &Path) -> Result<Value, Vec<ManifestError>> {
+        Manifest::resolved(file).map(|(document, _)| document)
+    }
+
+    /// Resolves manifest document along with origins of its fields
+    fn resolved(file: &Path) -> Result<(Value, Origins), Vec<ManifestError>> {
+        let error = |file: &Path, message| {
+            vec![ManifestError::new(
+                file,
+                &Locations::default(),
+                String::new(),
+                message,
+            )]
+        };
+        fs::metadata(file).map_err(|e| error(file, format!("Failed to read manifest: {}", e)))?;
+        let document = resolve::read(file).map_err(|e| error(file, e))?;
+        resolve::resolve(file, document).map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|(origin, message)| ManifestError::at(origin, message))
+                .collect()
+        })
+    }
+
+    /// Loads manifest, when it is invalid pipeline entries are checked one by one,
+    /// so all broken entries are reported at once.
+    pub fn from(file: PathBuf) -> Result<Self, Vec<ManifestError>> {
+        let (mut document, origins) = Manifest::resolved(&file)?;
+        let error =
+            |(path, message): (String, String)| ManifestError::at(origins.of(&path), message);
+        let first = match deserialize::<Manifest>(document.clone(), "") {
+            Ok(mut manifest) => {
+                let errors = manifest.check();
+                return if errors.is_empty() {
+                    manifest.origins = origins;
+                    Ok(manifest)
+                } else {
+                    Err(errors.into_iter().map(error).collect())
+                };
+            }
+            Err(e) => e,
+        };
+        let mut errors = Vec::new();
+        if let Some(Value::Array(entries)) = document.pointer_mut("/pipeline/test") {
+            for (index, entry) in entries.drain(..).enumerate() {
+                let path = format!("pipeline.test[{}]", index);
+                if let Err(e) = deserialize::<PipelineEntry>(entry, &path) {
+                    errors.push(e);
+                }
+            }
+        }
+        if let Err(e) = deserialize::<Manifest>(document, "") {
+            errors.push(e);
+        }
+        if errors.is_empty() {
+            errors.push(first);
+        }
+        Err(errors.into_iter().map(error).collect())
+    }
+}
+
+impl Manifest {
+    /// Creates error of field located in the file and at the path which define it
+    pub fn error(&self, path: String, message: String) -> ManifestError {
+        ManifestError::at(self.origins.of(&path), message)
+    }
+
+    /// Returns path and problem of each reference to unknown step
+    fn check(&self) -> Vec<(String, String)> {
+        let names: HashSet<&str> = self.pipeline.test.iter().map(|e| e.name.as_str()).collect();
+        self.resources
+            .iter()
+            .enumerate()
+            .filter_map(|(index, resource)| match &resource.step {
+                Some(step) if !names.contains(step.as_str()) => Some((
+                    format!("resources[{}].step", index),
+                    format!("Unknown step '{}'", step),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl ManifestError {
+    pub fn new(file: &Path, locations: &Locations, path: String, message: String) -> Self {
+        ManifestError {
+            file: file.to_owned(),
+            line: locations.line(&path),
+            path,
+            message,
+        }
+    }
+
+    /// Creates error of field defined at origin, line is looked up in the file of origin
+    pub fn at(origin: Origin, message: String) -> Self {
+        ManifestError::new(
+            &origin.file,
+            &Locations::read(&origin.file),
+            origin.path,
+            message,
+        )
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if !self.path.is_empty() {
+            write!(f, ": {}", self.path)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Deserializes part of manifest, error is returned with path of the field prefixed by path of the part
+fn deserialize<T: DeserializeOwned>(value: Value, prefix: &str) -> Result<T, (String, String)> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let path = location::path(e.path());
+        let path = match (prefix, path.as_str()) {
+            (prefix, "") => prefix.to_owned(),
+            ("", path) => path.to_owned(),
+            (prefix, path) => location::join(prefix, path),
+        };
+        (path, e.into_inner().to_string())
+    })
+}
+
+impl Matcher {
+    pub fn pattern(&self) -> &Regex {
+        match self {
+            Matcher::Pattern(pattern) => pattern,
+            Matcher::Mode { pattern, .. } => pattern,
+        }
+    }
+
+    pub fn mode(&self) -> &MatchMode {
+        match self {
+            Matcher::Pattern(_) => &MatchMode::Every,
+            Matcher::Mode { mode, .. } => mode,
+        }
+    }
+}
 
-        config.try_into()
+impl Functor {
+    /// Returns names of variables which values are compared by assertions
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            Functor::Assert { function, .. } => function
+                .params()
+                .into_iter()
+                .filter_map(|param| match param {
+                    AssertParamValueVar::Var(name) => Some(name.as_str()),
+                    AssertParamValueVar::Value(_) => None,
+                })
+                .collect(),
+            Functor::Matches(_) => Vec::new(),
+            Functor::All(functors) | Functor::Any(functors) => {
+                functors.iter().flat_map(Functor::variables).collect()
+            }
+            Functor::Not(functor) => functor.variables(),
+        }
     }
 }
 
-impl PipelineEntry {
-    pub fn generate_request_uri(&self) -> Uri {
-        let uri_string: String = self.request.clone();
-        let template = liquid::ParserBuilder::with_stdlib()
-            .build()
-            .unwrap()
-            .parse(uri_string.as_str())
-            .unwrap();
-        Uri::try_from(&template.render(&self.vars).unwrap()).unwrap()
+impl AssertFunction {
+    fn params(&self) -> Vec<&AssertParamValueVar> {
+        match self {
+            AssertFunction::Equal(param)
+            | AssertFunction::NotEqual(param)
+            | AssertFunction::Gt(param)
+            | AssertFunction::Ge(param)
+            | AssertFunction::Lt(param)
+            | AssertFunction::Le(param)
+            | AssertFunction::Contains(param)
+            | AssertFunction::StartsWith(param)
+            | AssertFunction::EndsWith(param)
+            | AssertFunction::OneOf(param)
+            | AssertFunction::Length(LengthExpectation::Exact(param)) => vec![param],
+            AssertFunction::Between { from, to } => vec![from, to],
+            AssertFunction::Length(LengthExpectation::Range { .. })
+            | AssertFunction::TypeIs(_)
+            | AssertFunction::Exists(_)
+            | AssertFunction::IsNil(_) => Vec::new(),
+        }
+    }
+}
+
+impl Display for AssertParamValueVar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AssertParamValueVar::Value(value) => write!(f, "{}", quoted(value)),
+            AssertParamValueVar::Var(name) => write!(f, "variable '{}'", name),
+        }
+    }
+}
+
+/// Describes assertion as it completes phrase "value must ..."
+impl Display for AssertFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AssertFunction::Equal(param) => write!(f, "equal {}", param),
+            AssertFunction::NotEqual(param) => write!(f, "not equal {}", param),
+            AssertFunction::Gt(param) => write!(f, "be greater than {}", param),
+            AssertFunction::Ge(param) => write!(f, "be greater than or equal to {}", param),
+            AssertFunction::Lt(param) => write!(f, "be less than {}", param),
+            AssertFunction::Le(param) => write!(f, "be less than or equal to {}", param),
+            AssertFunction::Contains(param) => write!(f, "contain {}", param),
+            AssertFunction::StartsWith(param) => write!(f, "start with {}", param),
+            AssertFunction::EndsWith(param) => write!(f, "end with {}", param),
+            AssertFunction::Length(LengthExpectation::Exact(param)) => {
+                write!(f, "have length {}", param)
+            }
+            AssertFunction::Length(LengthExpectation::Range { from, to }) => {
+                write!(f, "have length between {} and {}", from, to)
+            }
+            AssertFunction::TypeIs(value_type) => {
+                write!(f, "be {}", format!("{:?}", value_type).to_lowercase())
+            }
+            AssertFunction::Exists(true) => write!(f, "exist"),
+            AssertFunction::Exists(false) => write!(f, "not exist"),
+            AssertFunction::IsNil(true) => write!(f, "be nil"),
+            AssertFunction::IsNil(false) => write!(f, "not be nil"),
+            AssertFunction::OneOf(param) => write!(f, "be one of {}", param),
+            AssertFunction::Between { from, to } => write!(f, "be between {} and {}", from, to),
+        }
+    }
+}
+
+/// Formats value for messages, scalars are quoted and other values are shown as liquid literals
+pub fn quoted(value: &liquid::model::Value) -> String {
+    use liquid::ValueView;
+    match value.as_scalar() {
+        Some(scalar) => format!("'{}'", scalar.to_kstr()),
+        None => value.source().to_string(),
+    }
+}
+
+impl Display for CaptureSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureSource::Body => write!(f, "body"),
+            CaptureSource::Status => write!(f, "status"),
+            CaptureSource::Headers => write!(f, "headers"),
+            CaptureSource::Header(name) => write!(f, "header:{}", name),
+            CaptureSource::Cookie(name) => write!(f, "cookie:{}", name),
+        }
+    }
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Templates {
+            request: Err("Template is not compiled".into()),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use serde_json::json;
+    use std::env;
+
+    #[test]
+    fn test_all_invalid_entries_reported_with_location() {
+        let file = env::temp_dir().join(format!("cannon-manifest-{}.yaml", std::process::id()));
+        fs::write(
+            &file,
+            r#"name: Broken
+collect: mongodb://localhost
+pipeline:
+  test:
+    - name: Method
+      request: http://localhost
+      method: "GE T"
+    - name: Valid
+      request: http://localhost
+    - name: Status
+      request: http://localhost
+      expect:
+        status: 6xx
+"#,
+        )
+        .unwrap();
+        let errors = Manifest::from(file.clone()).unwrap_err();
+        fs::remove_file(&file).unwrap();
+
+        let errors: Vec<(Option<usize>, &str)> = errors
+            .iter()
+            .map(|error| (error.line, error.path.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (Some(7), "pipeline.test[0].method"),
+                (Some(13), "pipeline.test[2].expect.status"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_expectations_report_underlying_problem() {
+        let error = |field: &str, value: Value| {
+            let mut entry = json!({ "name": "Step", "request": "http://localhost" });
+            entry[field] = value;
+            deserialize::<PipelineEntry>(entry, "pipeline.test[0]").unwrap_err()
+        };
+        let on = |functor: Value| json!([{ "as": "id", "on": [functor] }]);
+
+        let status = error("expect", json!({ "status": "6xx" }));
+        assert_eq!(status.0, "pipeline.test[0].expect.status");
+        assert!(status.1.starts_with("Invalid status class '6xx'"));
+        let code = error("expect", json!({ "status": [200, 1000] }));
+        assert!(code.1.starts_with("Invalid status code 1000"));
+        let header = error(
+            "expect",
+            json!({ "headers": { "Location": { "matches": "(" } } }),
+        );
+        assert_eq!(header.0, "pipeline.test[0].expect.headers");
+        assert!(header.1.starts_with("header 'Location': Invalid regex '('"));
+        let matcher = error("capture", on(json!({ "matches": "[a-" })));
+        assert!(matcher.1.starts_with("Invalid regex '[a-'"));
+        let length = error(
+            "capture",
+            on(json!({ "assert": { "length": { "from": 1 } } })),
+        );
+        assert!(length
+            .1
+            .starts_with("Invalid length range: missing field `to`"));
+    }
+
+    #[test]
+    fn test_errors_of_included_steps_are_located_in_their_files() {
+        let dir = env::temp_dir().join(format!("cannon-origins-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("shared.yaml"),
+            "pipeline:\n  test:\n    - name: Shared\n      request: http://localhost\n      method: \"P OST\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("main.yaml"),
+            r#"name: Main
+collect: http://localhost
+include: shared.yaml
+pipeline:
+  test:
+    - name: First
+      request: http://localhost
+    - name: Second
+      request: http://localhost
+      method: "GE T"
+"#,
+        )
+        .unwrap();
+        let errors = Manifest::from(dir.join("main.yaml")).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let errors: Vec<(PathBuf, Option<usize>, &str)> = errors
+            .iter()
+            .map(|error| (error.file.clone(), error.line, error.path.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (dir.join("shared.yaml"), Some(5), "pipeline.test[0].method"),
+                (dir.join("main.yaml"), Some(10), "pipeline.test[1].method"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resource_of_unknown_step_is_reported() {
+        let file = env::temp_dir().join(format!("cannon-resource-{}.yaml", std::process::id()));
+        fs::write(
+            &file,
+            "name: Resources\ncollect: http://localhost\nresources:\n  - uri: file://./rows.csv\n    step: Missing\npipeline:\n  test:\n    - name: Login\n      request: http://localhost\n",
+        )
+        .unwrap();
+        let errors = Manifest::from(file.clone()).unwrap_err();
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, Some(5));
+        assert_eq!(errors[0].path, "resources[0].step");
+        assert_eq!(errors[0].message, "Unknown step 'Missing'");
     }
 }
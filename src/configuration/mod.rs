@@ -1,4 +1,8 @@
 pub mod command_line;
 pub mod constants;
 pub mod deserialize;
+pub mod discover;
+pub mod environment;
+pub mod location;
 pub mod manifest;
+pub mod resolve;
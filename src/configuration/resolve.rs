@@ -0,0 +1,473 @@
+use crate::configuration::location;
+use config::{Config, File};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Fields of step merged with the same fields of its template key by key, other fields are replaced
+const MERGED: [&str; 2] = ["headers", "vars"];
+
+/// File and path of field in the manifest which defines it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub file: PathBuf,
+    pub path: String,
+}
+
+/// Origins of fields of resolved manifest by their paths, steps and other fields
+/// may be defined by included manifests and step templates
+#[derive(Debug, Default)]
+pub struct Origins {
+    file: PathBuf,
+    sources: HashMap<String, Origin>,
+}
+
+/// Origins of steps and other fields of manifest merged with manifests it includes
+#[derive(Default)]
+struct Sources {
+    steps: Vec<Origin>,
+    fields: HashMap<String, Origin>,
+}
+
+/// Reads manifest document from file of any supported format
+pub fn read(file: &Path) -> Result<Value, String> {
+    let mut config = Config::new();
+    config
+        .merge(File::from(file.to_owned()))
+        .and_then(|config| config.clone().try_into())
+        .map_err(|e| e.to_string())
+}
+
+/// Resolves `include` of other manifests and `extends` of step templates,
+/// returns origin and problem of each include or step which can't be resolved.
+pub fn resolve(file: &Path, document: Value) -> Result<(Value, Origins), Vec<(Origin, String)>> {
+    let mut errors = Vec::new();
+    let mut included = vec![canonical(file)];
+    let (mut document, sources) = include(file, document, &mut included, &mut errors);
+    let mut origins = Origins {
+        file: file.to_owned(),
+        sources: HashMap::new(),
+    };
+    for (index, origin) in sources.steps.into_iter().enumerate() {
+        origins
+            .sources
+            .insert(format!("pipeline.test[{}]", index), origin);
+    }
+    let templates = match document.as_object_mut().and_then(|d| d.remove("templates")) {
+        Some(Value::Object(templates)) => templates,
+        _ => Map::new(),
+    };
+    if let Some(Value::Array(entries)) = document.pointer_mut("/pipeline/test") {
+        for (index, entry) in entries.iter_mut().enumerate() {
+            let path = format!("pipeline.test[{}]", index);
+            match extend(entry.clone(), &templates, &mut Vec::new()) {
+                Ok((resolved, defined)) => {
+                    *entry = resolved;
+                    for (key, name) in defined {
+                        if let Some(template) = sources.fields.get(&format!("templates.{}", name)) {
+                            let origin = Origin {
+                                file: template.file.clone(),
+                                path: location::join(&template.path, &key),
+                            };
+                            origins.sources.insert(location::join(&path, &key), origin);
+                        }
+                    }
+                }
+                Err(e) => errors.push((origins.of(&location::join(&path, "extends")), e)),
+            }
+        }
+    }
+    origins.sources.extend(sources.fields);
+    if errors.is_empty() {
+        Ok((document, origins))
+    } else {
+        Err(errors)
+    }
+}
+
+/// Returns canonical paths of manifests included by manifest directly or through other included ones
+pub fn included(file: &Path) -> Vec<PathBuf> {
+    let mut included = Vec::new();
+    let mut pending = vec![file.to_owned()];
+    while let Some(file) = pending.pop() {
+        let paths = match read(&file).map(|mut document| document["include"].take()) {
+            Ok(Value::Array(paths)) => paths,
+            Ok(Value::Null) | Err(_) => continue,
+            Ok(path) => vec![path],
+        };
+        let dir = file.parent().unwrap_or_else(|| Path::new(""));
+        for path in paths.iter().filter_map(Value::as_str) {
+            let path = dir.join(path);
+            if !included.contains(&canonical(&path)) {
+                included.push(canonical(&path));
+                pending.push(path);
+            }
+        }
+    }
+    included
+}
+
+/// Merges included manifests in order, manifest itself overrides values of included ones
+fn include(
+    file: &Path,
+    mut document: Value,
+    included: &mut Vec<PathBuf>,
+    errors: &mut Vec<(Origin, String)>,
+) -> (Value, Sources) {
+    let paths = match document.as_object_mut().and_then(|d| d.remove("include")) {
+        Some(Value::Array(paths)) => paths,
+        Some(path) => vec![path],
+        None => {
+            let sources = Sources::of(file, &document);
+            return (document, sources);
+        }
+    };
+    let dir = file.parent().unwrap_or_else(|| Path::new(""));
+    let mut merged = Value::Object(Map::new());
+    let mut sources = Sources::default();
+    for (index, path) in paths.iter().enumerate() {
+        let at = Origin {
+            file: file.to_owned(),
+            path: format!("include[{}]", index),
+        };
+        let path = match path.as_str() {
+            Some(path) => dir.join(path),
+            None => {
+                errors.push((at, "Path of manifest file expected".into()));
+                continue;
+            }
+        };
+        if included.contains(&canonical(&path)) {
+            errors.push((at, format!("Circular include of '{}'", path.display())));
+            continue;
+        }
+        match read(&path) {
+            Ok(document) => {
+                included.push(canonical(&path));
+                let (document, included_sources) = include(&path, document, included, errors);
+                included.pop();
+                sources.merge(&merged, &document, included_sources);
+                merge_document(&mut merged, document);
+            }
+            Err(e) => errors.push((at, format!("Failed to include '{}': {}", path.display(), e))),
+        }
+    }
+    sources.merge(&merged, &document, Sources::of(file, &document));
+    merge_document(&mut merged, document);
+    (merged, sources)
+}
+
+/// Resolves chain of templates of step, step overrides fields of its template.
+/// Returns resolved step and name of template defining each field which step doesn't define itself.
+fn extend(
+    mut entry: Value,
+    templates: &Map<String, Value>,
+    chain: &mut Vec<String>,
+) -> Result<(Value, HashMap<String, String>), String> {
+    let name = match entry.as_object_mut().and_then(|e| e.remove("extends")) {
+        None => return Ok((entry, HashMap::new())),
+        Some(Value::String(name)) => name,
+        Some(_) => return Err("Name of step template expected".into()),
+    };
+    if chain.contains(&name) {
+        return Err(format!("Circular extends of step template '{}'", name));
+    }
+    let template = templates
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| format!("Unknown step template '{}'", name))?;
+    let own: Vec<String> = template
+        .as_object()
+        .map(|template| {
+            template
+                .keys()
+                .filter(|key| *key != "extends")
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    chain.push(name.clone());
+    let (mut resolved, mut defined) = extend(template, templates, chain)?;
+    defined.extend(own.into_iter().map(|key| (key, name.clone())));
+    match (&mut resolved, entry) {
+        (Value::Object(resolved), Value::Object(entry)) => {
+            for (key, value) in entry {
+                defined.remove(&key);
+                match resolved.get_mut(&key) {
+                    Some(base) if MERGED.contains(&key.as_str()) => merge(base, value, 1),
+                    _ => {
+                        resolved.insert(key, value);
+                    }
+                }
+            }
+        }
+        (resolved, entry) => {
+            defined.clear();
+            *resolved = entry
+        }
+    }
+    Ok((resolved, defined))
+}
+
+/// Merges manifests deeply, except step templates which are replaced by name
+/// and steps of pipeline which are appended to steps of the manifests merged before
+fn merge_document(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(mut overlay)) => {
+            let steps = overlay.get_mut("pipeline").and_then(|p| p.get_mut("test"));
+            if let (Some(Value::Array(base)), Some(Value::Array(steps))) = (
+                base.get_mut("pipeline").and_then(|p| p.get_mut("test")),
+                steps,
+            ) {
+                steps.splice(0..0, base.drain(..));
+            }
+            for (key, value) in overlay {
+                let depth = if key == "templates" { 1 } else { usize::MAX };
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value, depth),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Merges objects up to given depth, deeper values and values of other types are replaced
+fn merge(base: &mut Value, overlay: Value, depth: usize) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) if depth > 0 => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value, depth - 1),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+pub fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Tells if document has steps of pipeline which are appended on merge
+fn has_steps(document: &Value) -> bool {
+    matches!(document.pointer("/pipeline/test"), Some(Value::Array(_)))
+}
+
+impl Origins {
+    /// Returns origin of field by origin of its closest parent, the manifest itself by default
+    pub fn of(&self, path: &str) -> Origin {
+        let mut parent = path;
+        loop {
+            if let Some(origin) = self.sources.get(parent) {
+                return Origin {
+                    file: origin.file.clone(),
+                    path: format!("{}{}", origin.path, &path[parent.len()..]),
+                };
+            }
+            if parent.is_empty() {
+                return Origin {
+                    file: self.file.clone(),
+                    path: path.to_owned(),
+                };
+            }
+            parent = &parent[..parent.rfind(['.', '[']).unwrap_or(0)];
+        }
+    }
+}
+
+impl Sources {
+    /// Sources of fields which manifest document defines itself
+    fn of(file: &Path, document: &Value) -> Self {
+        let origin = |path: String| Origin {
+            file: file.to_owned(),
+            path,
+        };
+        let mut sources = Sources::default();
+        for (key, value) in document.as_object().into_iter().flatten() {
+            let children: Vec<&String> = match (key.as_str(), value) {
+                ("pipeline", Value::Object(pipeline)) => {
+                    pipeline.keys().filter(|key| *key != "test").collect()
+                }
+                ("templates", Value::Object(templates)) => templates.keys().collect(),
+                _ => {
+                    sources.fields.insert(key.clone(), origin(key.clone()));
+                    continue;
+                }
+            };
+            for child in children {
+                let path = location::join(key, child);
+                sources.fields.insert(path.clone(), origin(path));
+            }
+        }
+        if let Some(Value::Array(steps)) = document.pointer("/pipeline/test") {
+            sources.steps = (0..steps.len())
+                .map(|index| origin(format!("pipeline.test[{}]", index)))
+                .collect();
+        }
+        sources
+    }
+
+    /// Adds sources of document merged over base document the same way as `merge_document` does
+    fn merge(&mut self, base: &Value, overlay: &Value, sources: Sources) {
+        if has_steps(base) && has_steps(overlay) {
+            self.steps.extend(sources.steps);
+        } else if overlay.pointer("/pipeline/test").is_some() {
+            self.steps = sources.steps;
+        }
+        self.fields.extend(sources.fields);
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use serde_json::json;
+    use std::{env, fs};
+
+    #[test]
+    fn test_step_extends_template_overriding_headers_vars_and_body() {
+        let document = json!({
+            "templates": {
+                "authorized": {
+                    "method": "POST",
+                    "headers": { "Authorization": "Bearer {{ token }}", "Accept": "text/plain" },
+                    "vars": { "user": "admin" }
+                },
+                "login": {
+                    "extends": "authorized",
+                    "name": "Login",
+                    "request": "http://localhost/login",
+                    "body": { "raw": "{{ user }}" }
+                }
+            },
+            "pipeline": { "test": [
+                {
+                    "extends": "login",
+                    "headers": { "Accept": "application/json" },
+                    "vars": { "password": "secret" },
+                    "body": { "json": { "user": "guest" } }
+                },
+                { "extends": "missing" }
+            ]}
+        });
+
+        let origin = |path: &str| Origin {
+            file: PathBuf::from("manifest.yaml"),
+            path: path.to_string(),
+        };
+        let errors = resolve(Path::new("manifest.yaml"), document.clone()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![(
+                origin("pipeline.test[1].extends"),
+                "Unknown step template 'missing'".to_string()
+            )]
+        );
+
+        let mut document = document;
+        document["pipeline"]["test"].as_array_mut().unwrap().pop();
+        let (resolved, origins) = resolve(Path::new("manifest.yaml"), document).unwrap();
+        assert_eq!(
+            origins.of("pipeline.test[0].method"),
+            origin("templates.authorized.method")
+        );
+        assert_eq!(
+            origins.of("pipeline.test[0].name"),
+            origin("templates.login.name")
+        );
+        assert_eq!(
+            origins.of("pipeline.test[0].body.raw"),
+            origin("pipeline.test[0].body.raw")
+        );
+        assert_eq!(
+            resolved,
+            json!({ "pipeline": { "test": [{
+                "name": "Login",
+                "method": "POST",
+                "request": "http://localhost/login",
+                "headers": { "Authorization": "Bearer {{ token }}", "Accept": "application/json" },
+                "vars": { "user": "admin", "password": "secret" },
+                "body": { "json": { "user": "guest" } }
+            }]}})
+        );
+    }
+
+    #[test]
+    fn test_included_manifests_are_merged_steps_kept_and_cycles_reported() {
+        let dir = env::temp_dir().join(format!("cannon-include-{}", std::process::id()));
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(
+            dir.join("shared/common.yaml"),
+            "vars:\n  host: shared\n  port: 80\ntemplates:\n  login:\n    name: Login\n    request: http://{{ host }}/login\npipeline:\n  test:\n    - name: Health\n      request: http://{{ host }}/health\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("shared/auth.json"),
+            r#"{"vars": {"token": "abc"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("loop.yaml"), "include: shared/../loop.yaml\n").unwrap();
+        let document = json!({
+            "include": ["shared/common.yaml", "shared/auth.json", "shared/missing.yaml"],
+            "vars": { "host": "local" },
+            "pipeline": { "test": [{ "extends": "login" }] }
+        });
+
+        let errors = resolve(&dir.join("manifest.yaml"), document.clone()).unwrap_err();
+        let mut document = document;
+        document["include"].as_array_mut().unwrap().pop();
+        let resolved = resolve(&dir.join("manifest.yaml"), document);
+        let circular = resolve(
+            &dir.join("loop.yaml"),
+            read(&dir.join("loop.yaml")).unwrap(),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0.path, "include[2]");
+        assert!(errors[0].1.starts_with("Failed to include"));
+        let (resolved, origins) = resolved.unwrap();
+        let origin = |file: &str, path: &str| Origin {
+            file: dir.join(file),
+            path: path.to_string(),
+        };
+        assert_eq!(
+            origins.of("pipeline.test[0].request"),
+            origin("shared/common.yaml", "pipeline.test[0].request")
+        );
+        assert_eq!(
+            origins.of("pipeline.test[1].request"),
+            origin("shared/common.yaml", "templates.login.request")
+        );
+        assert_eq!(
+            origins.of("pipeline.test[1].method"),
+            origin("manifest.yaml", "pipeline.test[0].method")
+        );
+        assert_eq!(
+            origins.of("vars.token"),
+            origin("manifest.yaml", "vars.token")
+        );
+        assert_eq!(
+            resolved,
+            json!({
+                "vars": { "host": "local", "port": 80, "token": "abc" },
+                "pipeline": { "test": [
+                    { "name": "Health", "request": "http://{{ host }}/health" },
+                    { "name": "Login", "request": "http://{{ host }}/login" }
+                ]}
+            })
+        );
+        let circular = circular.unwrap_err();
+        assert_eq!(circular[0].0, origin("loop.yaml", "include[0]"));
+        assert!(circular[0].1.starts_with("Circular include"));
+    }
+}
@@ -19,50 +19,304 @@ extern crate log;
 mod app;
 mod configuration;
 
+use futures::stream::{self, StreamExt};
+use liquid::Object;
 use log::LevelFilter;
 use signal_hook::{iterator::Signals, SIGINT};
-use std::{path::PathBuf, process::exit, thread};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use std::{process::exit, thread};
+use structopt::clap::{Error as ClapError, ErrorKind};
 use structopt::StructOpt;
 
-use self::app::App;
+use self::app::report::{junit, RunReport, Status, StepResult, SuiteReport};
+use self::app::{App, Filter, Secrets, Snapshots};
 use self::{
-    configuration::command_line::{LogLevel, Opt},
-    configuration::manifest::Manifest,
+    configuration::command_line::{Command, LogLevel, Opt, ReportKind, ReportOutput},
+    configuration::constants::exit_code,
+    configuration::discover,
+    configuration::environment,
+    configuration::location::Locations,
+    configuration::manifest::{Manifest, ManifestError},
 };
 
 #[tokio::main]
 async fn main() {
     let options = Opt::from_args();
-    let signals = Signals::new(&[SIGINT]).unwrap();
+    let signals = Signals::new([SIGINT]).unwrap();
 
     thread::spawn(move || {
-        for sig in signals.forever() {
+        if let Some(sig) = signals.forever().next() {
             info!("Received signal {:?}, stopping", sig);
             exit(0);
         }
     });
 
-    let manifest = Manifest::from(options.file);
+    let secrets = Secrets::default();
 
     init_logging(
         options.logging.unwrap_or(LogLevel::Info).into(),
         &options.log_output_file,
+        secrets.clone(),
     );
 
-    match manifest {
-        Ok(manifest) => {
-            debug!("Initiated configuration {:#?}", manifest);
-            let app = App::new(manifest);
-            app.run().await;
+    let random_seed = options.seed.unwrap_or_else(rand::random);
+    let file = match (&options.command, &options.file) {
+        (Some(Command::Validate { file }), _) => exit(validate(file.clone())),
+        (Some(Command::Run { paths, jobs, setup }), _) => {
+            info!("Using random seed {}", random_seed);
+            let suite = Suite {
+                options: &options,
+                secrets: &secrets,
+                seed: random_seed,
+            };
+            exit(suite.run(paths, *jobs, setup).await)
         }
-        Err(e) => error!("Failed to load manifest file configuration {}", e),
+        (None, Some(file)) => file.clone(),
+        (None, None) => ClapError::with_description(
+            "The following required arguments were not provided: <file>",
+            ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+    };
+    if options.print_resolved {
+        exit(print_resolved(&file, &secrets));
     }
+    info!("Using random seed {}", random_seed);
+    let app = match load(&file, &options, &secrets, random_seed, filter(&options)) {
+        Ok(app) => app,
+        Err(errors) => {
+            for e in errors {
+                error!("Failed to load manifest {}", e);
+            }
+            exit(exit_code::INVALID_MANIFEST);
+        }
+    };
+    let report = app.run().await;
+    for output in &options.report {
+        let written = match output.kind {
+            ReportKind::Junit => junit::write(&report, &output.path),
+        };
+        log_written(output, written);
+    }
+    exit(report.exit_code());
+}
+
+/// Loads manifest and its environment, templates are checked before any request is sent
+fn load(
+    file: &Path,
+    options: &Opt,
+    secrets: &Secrets,
+    seed: u64,
+    filter: Filter,
+) -> Result<App, Vec<ManifestError>> {
+    let manifest = Manifest::from(file.to_owned())?;
+    let document = Manifest::resolve(file)?;
+    let env = environment::load(file, &document, options.env_file.as_deref(), &manifest.env)
+        .map_err(|e| {
+            vec![ManifestError::new(
+                file,
+                &Locations::default(),
+                String::new(),
+                e,
+            )]
+        })?;
+    let strict = options.strict || manifest.strict;
+    let app = App::new(manifest)
+        .with_strict(strict)
+        .with_secrets(secrets.clone())
+        .with_snapshots(Snapshots::new(file, options.update_snapshots))
+        .with_seed(seed)
+        .with_env(env)
+        .with_filter(filter);
+    let problems = app.validate();
+    if problems.is_empty() {
+        Ok(app)
+    } else {
+        Err(problems)
+    }
+}
+
+/// Selects tests by `--tag`, `--skip-tag` and `--grep` options
+fn filter(options: &Opt) -> Filter {
+    Filter {
+        tags: options.tags.iter().cloned().collect(),
+        skip_tags: options.skip_tags.iter().cloned().collect(),
+        grep: options.grep.clone(),
+    }
+}
+
+/// Run of many manifests, every manifest has its own globals
+struct Suite<'a> {
+    options: &'a Opt,
+    secrets: &'a Secrets,
+    seed: u64,
+}
+
+impl Suite<'_> {
+    /// Runs setup manifests one by one, then the rest of manifests at most `jobs` at once
+    /// with globals captured by setup, returns exit code of the worst manifest.
+    /// Setup manifests run all their tests, as other manifests depend on globals they capture.
+    async fn run(&self, patterns: &[String], jobs: usize, setup: &[PathBuf]) -> i32 {
+        let files = match discover::manifests(patterns, setup) {
+            Ok(files) => files,
+            Err(e) => {
+                error!("{}", e);
+                return exit_code::INVALID_MANIFEST;
+            }
+        };
+        info!(
+            "Starting suite of {} manifest(s) with {} setup manifest(s)",
+            files.len(),
+            setup.len()
+        );
+        let started = Instant::now();
+        let mut suite = SuiteReport::default();
+        let mut shared = Object::new();
+        for file in setup {
+            let (report, globals) = self.run_manifest(file, &shared, Filter::default()).await;
+            shared.extend(globals);
+            suite.reports.push(report);
+        }
+        let shared = &shared;
+        let filter = &filter(self.options);
+        let reports: Vec<RunReport> = stream::iter(files.iter())
+            .map(|file| async move { self.run_manifest(file, shared, filter.clone()).await.0 })
+            .buffered(jobs.max(1))
+            .collect()
+            .await;
+        suite.reports.extend(reports);
+        suite.elapsed = started.elapsed();
+        info!(
+            "Finished suite in {} ms: {} passed, {} failed, {} errors",
+            suite.elapsed.as_millis(),
+            suite.count(Status::Passed),
+            suite.count(Status::Failed),
+            suite.count(Status::Error)
+        );
+        for output in &self.options.report {
+            let written = match output.kind {
+                ReportKind::Junit => junit::write_suite(&suite, &output.path),
+            };
+            log_written(output, written);
+        }
+        suite.exit_code()
+    }
+
+    /// Runs manifest with given globals, manifest which fails to load is reported as errored step
+    async fn run_manifest(
+        &self,
+        file: &Path,
+        globals: &Object,
+        filter: Filter,
+    ) -> (RunReport, Object) {
+        let seed = manifest_seed(self.seed, file);
+        debug!("Using random seed {} for manifest {}", seed, file.display());
+        match load(file, self.options, self.secrets, seed, filter) {
+            Ok(app) => {
+                let app = app.with_globals(globals.clone());
+                (app.run().await, app.globals())
+            }
+            Err(errors) => {
+                let mut report = RunReport::new(&file.display().to_string());
+                report.invalid = true;
+                let mut result = StepResult::new("load");
+                for e in errors {
+                    result.error(format!("Failed to load manifest {}", e));
+                }
+                report.steps.push(result);
+                (report, Object::new())
+            }
+        }
+    }
+}
+
+/// Derives seed of manifest from seed of suite, so every manifest generates its own random and fake data
+/// and the suite is replayed exactly with the same seed. FNV-1a hash is used as it's stable between builds.
+fn manifest_seed(seed: u64, file: &Path) -> u64 {
+    file.to_string_lossy()
+        .bytes()
+        .fold(seed ^ 0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
 }
 
-fn init_logging(level: LevelFilter, output: &Option<PathBuf>) {
+fn log_written(output: &ReportOutput, written: io::Result<()>) {
+    match written {
+        Ok(()) => info!("Report written to {}", output.path.display()),
+        Err(e) => error!("Failed to write report {}: {}", output.path.display(), e),
+    }
+}
+
+/// Reports all problems of manifest, templates are parsed without sending any request
+fn validate(file: PathBuf) -> i32 {
+    let problems = match Manifest::from(file.clone()) {
+        Ok(manifest) => App::new(manifest).validate(),
+        Err(errors) => errors,
+    };
+    for problem in &problems {
+        error!("{}", problem);
+    }
+    if problems.is_empty() {
+        info!("Manifest {} is valid", file.display());
+        exit_code::SUCCESS
+    } else {
+        error!(
+            "Manifest {} has {} problem(s)",
+            file.display(),
+            problems.len()
+        );
+        exit_code::INVALID_MANIFEST
+    }
+}
+
+/// Prints resolved manifest as JSON, values of variables listed in `secrets` are masked
+fn print_resolved(file: &Path, secrets: &Secrets) -> i32 {
+    let document = match Manifest::resolve(file) {
+        Ok(document) => document,
+        Err(errors) => {
+            for e in errors {
+                error!("Failed to load manifest {}", e);
+            }
+            return exit_code::INVALID_MANIFEST;
+        }
+    };
+    let empty = Vec::new();
+    let entries = document
+        .pointer("/pipeline/test")
+        .and_then(|entries| entries.as_array())
+        .unwrap_or(&empty);
+    let scopes: Vec<_> = [document.get("vars"), document.get("env")]
+        .iter()
+        .flatten()
+        .copied()
+        .chain(entries.iter().filter_map(|entry| entry.get("vars")))
+        .collect();
+    let names = document.get("secrets").and_then(|names| names.as_array());
+    for name in names.into_iter().flatten().filter_map(|name| name.as_str()) {
+        for value in scopes.iter().filter_map(|scope| scope.get(name)) {
+            match value.as_str() {
+                Some(value) => secrets.add(value),
+                None => secrets.add(&value.to_string()),
+            }
+        }
+    }
+    match serde_json::to_string_pretty(&document) {
+        Ok(document) => {
+            println!("{}", secrets.mask(&document));
+            exit_code::SUCCESS
+        }
+        Err(e) => {
+            error!("Failed to print manifest {}: {}", file.display(), e);
+            exit_code::INVALID_MANIFEST
+        }
+    }
+}
+
+fn init_logging(level: LevelFilter, output: &Option<PathBuf>, secrets: Secrets) {
     let mut dispatcher = fern::Dispatch::new()
-        // Perform allocation-free log formatting
-        .format(|out, message, record| {
+        .format(move |out, message, record| {
             out.finish(format_args!(
                 "{}[{}:{}][{}] {}",
                 chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
@@ -72,7 +326,7 @@ fn init_logging(level: LevelFilter, output: &Option<PathBuf>) {
                     .map(|v| v.to_string())
                     .unwrap_or_else(|| "".to_owned()),
                 record.level(),
-                message
+                secrets.mask(&message.to_string())
             ))
         })
         .level(level)
@@ -84,3 +338,18 @@ fn init_logging(level: LevelFilter, output: &Option<PathBuf>) {
     dispatcher.apply().unwrap();
     info!("Logging level {} enabled", level);
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_manifests_of_suite_get_own_reproducible_seeds() {
+        let users = manifest_seed(42, Path::new("tests/users.yaml"));
+
+        assert_eq!(users, manifest_seed(42, Path::new("tests/users.yaml")));
+        assert_ne!(users, manifest_seed(42, Path::new("tests/orders.yaml")));
+        assert_ne!(users, manifest_seed(43, Path::new("tests/users.yaml")));
+    }
+}